@@ -0,0 +1,50 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::master::EngineAction;
+
+mod eval;
+pub use eval::{eval, truthy, Context};
+
+mod parser;
+pub use parser::{parse, Expr};
+
+#[derive(Error, Debug)]
+pub enum ExpressionError {
+    #[error("Syntax error: {0}")]
+    Syntax(String),
+    #[error("Unknown variable or function {0}")]
+    Unknown(String),
+    #[error("Wrong number of arguments for {0}")]
+    Arguments(String),
+    #[error("Type error: {0}")]
+    Type(String),
+}
+
+// Parses and evaluates an expression against the incoming action and info
+pub fn evaluate(
+    source: &str,
+    info: &Value,
+    action: &EngineAction,
+) -> Result<Value, ExpressionError> {
+    eval(&parse(source)?, &Context::new(info, action))
+}
@@ -0,0 +1,324 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use super::parser::Expr;
+use super::ExpressionError;
+use crate::master::EngineAction;
+
+// The values an expression can read
+pub struct Context<'a> {
+    pub payload: Value,
+    pub topic: &'a str,
+    pub info: &'a Value,
+    // Values provided by the calling function, null if none
+    pub inputs: Value,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(info: &'a Value, action: &'a EngineAction) -> Self {
+        Context {
+            payload: serde_json::from_slice(&action.payload)
+                .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload))),
+            topic: &action.topic,
+            info,
+            inputs: Value::Null,
+        }
+    }
+
+    pub fn with_inputs(mut self, inputs: Value) -> Self {
+        self.inputs = inputs;
+        self
+    }
+
+    fn variable(&self, name: &str) -> Result<Value, ExpressionError> {
+        match name {
+            "payload" => Ok(self.payload.clone()),
+            "topic" => Ok(json!(self.topic)),
+            "segments" => Ok(json!(self.topic.split('/').collect::<Vec<&str>>())),
+            "info" => Ok(self.info.clone()),
+            "inputs" => Ok(self.inputs.clone()),
+            "timestamp" => Ok(self.info["_timestamp"].clone()),
+            _ => Err(ExpressionError::Unknown(name.into())),
+        }
+    }
+}
+
+pub fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+pub(super) fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9e15 {
+        json!(n as i64)
+    } else {
+        json!(n)
+    }
+}
+
+fn as_number(value: &Value) -> Result<f64, ExpressionError> {
+    match value {
+        Value::Number(n) => Ok(n.as_f64().unwrap_or(0.0)),
+        Value::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        Value::Null => Ok(0.0),
+        Value::String(s) => s
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| ExpressionError::Type(format!("{s:?} is not a number"))),
+        other => Err(ExpressionError::Type(format!("{other} is not a number"))),
+    }
+}
+
+fn as_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64() == r.as_f64(),
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Result<std::cmp::Ordering, ExpressionError> {
+    match (left, right) {
+        (Value::String(l), Value::String(r)) => Ok(l.cmp(r)),
+        _ => as_number(left)?
+            .partial_cmp(&as_number(right)?)
+            .ok_or_else(|| ExpressionError::Type("Cannot compare NaN".into())),
+    }
+}
+
+pub fn eval(expr: &Expr, context: &Context) -> Result<Value, ExpressionError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Variable(name) => context.variable(name),
+        Expr::Member(object, name) => Ok(eval(object, context)?[name.as_str()].clone()),
+        Expr::Index(object, index) => {
+            let object = eval(object, context)?;
+            match eval(index, context)? {
+                Value::String(key) => Ok(object[key.as_str()].clone()),
+                index => {
+                    let i = as_number(&index)?;
+                    let len = object.as_array().map_or(0, |a| a.len()) as f64;
+                    // Negative indexes count from the end
+                    let i = if i < 0.0 { len + i } else { i };
+                    Ok(if i >= 0.0 {
+                        object[i as usize].clone()
+                    } else {
+                        Value::Null
+                    })
+                }
+            }
+        }
+        Expr::Unary(op, operand) => {
+            let value = eval(operand, context)?;
+            match *op {
+                "!" => Ok(json!(!truthy(&value))),
+                _ => Ok(number(-as_number(&value)?)),
+            }
+        }
+        Expr::Binary(op, left, right) => match *op {
+            "&&" => {
+                let l = eval(left, context)?;
+                if truthy(&l) {
+                    eval(right, context)
+                } else {
+                    Ok(l)
+                }
+            }
+            "||" => {
+                let l = eval(left, context)?;
+                if truthy(&l) {
+                    Ok(l)
+                } else {
+                    eval(right, context)
+                }
+            }
+            _ => binary(op, &eval(left, context)?, &eval(right, context)?),
+        },
+        Expr::Conditional(condition, then, otherwise) => {
+            if truthy(&eval(condition, context)?) {
+                eval(then, context)
+            } else {
+                eval(otherwise, context)
+            }
+        }
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|a| eval(a, context))
+                .collect::<Result<Vec<Value>, ExpressionError>>()?;
+            call(name, &args)
+        }
+    }
+}
+
+fn binary(op: &str, l: &Value, r: &Value) -> Result<Value, ExpressionError> {
+    use std::cmp::Ordering;
+    match op {
+        "==" => Ok(json!(equals(l, r))),
+        "!=" => Ok(json!(!equals(l, r))),
+        "<" => Ok(json!(compare(l, r)? == Ordering::Less)),
+        "<=" => Ok(json!(compare(l, r)? != Ordering::Greater)),
+        ">" => Ok(json!(compare(l, r)? == Ordering::Greater)),
+        ">=" => Ok(json!(compare(l, r)? != Ordering::Less)),
+        "+" if l.is_string() || r.is_string() => Ok(json!(as_string(l) + as_string(r).as_str())),
+        "+" => Ok(number(as_number(l)? + as_number(r)?)),
+        "-" => Ok(number(as_number(l)? - as_number(r)?)),
+        "*" => Ok(number(as_number(l)? * as_number(r)?)),
+        "/" | "%" => {
+            let divisor = as_number(r)?;
+            if divisor == 0.0 {
+                return Err(ExpressionError::Type("Division by zero".into()));
+            }
+            if op == "/" {
+                Ok(number(as_number(l)? / divisor))
+            } else {
+                Ok(number(as_number(l)? % divisor))
+            }
+        }
+        _ => Err(ExpressionError::Syntax(format!("Unknown operator {op}"))),
+    }
+}
+
+fn arity(name: &str, args: &[Value], min: usize, max: usize) -> Result<(), ExpressionError> {
+    if args.len() < min || args.len() > max {
+        Err(ExpressionError::Arguments(name.into()))
+    } else {
+        Ok(())
+    }
+}
+
+fn call(name: &str, args: &[Value]) -> Result<Value, ExpressionError> {
+    match name {
+        "len" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(match &args[0] {
+                Value::String(s) => s.chars().count(),
+                Value::Array(a) => a.len(),
+                Value::Object(o) => o.len(),
+                _ => 0,
+            }))
+        }
+        "lower" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(as_string(&args[0]).to_lowercase()))
+        }
+        "upper" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(as_string(&args[0]).to_uppercase()))
+        }
+        "trim" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(as_string(&args[0]).trim()))
+        }
+        "contains" => {
+            arity(name, args, 2, 2)?;
+            Ok(json!(match &args[0] {
+                Value::Array(a) => a.iter().any(|v| equals(v, &args[1])),
+                Value::Object(o) => o.contains_key(&as_string(&args[1])),
+                value => as_string(value).contains(&as_string(&args[1])),
+            }))
+        }
+        "starts_with" => {
+            arity(name, args, 2, 2)?;
+            Ok(json!(as_string(&args[0]).starts_with(&as_string(&args[1]))))
+        }
+        "ends_with" => {
+            arity(name, args, 2, 2)?;
+            Ok(json!(as_string(&args[0]).ends_with(&as_string(&args[1]))))
+        }
+        "substr" => {
+            arity(name, args, 2, 3)?;
+            let s = as_string(&args[0]);
+            let start = as_number(&args[1])?.max(0.0) as usize;
+            let chars = s.chars().skip(start);
+            Ok(json!(match args.get(2) {
+                Some(len) => chars
+                    .take(as_number(len)?.max(0.0) as usize)
+                    .collect::<String>(),
+                None => chars.collect::<String>(),
+            }))
+        }
+        "str" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(as_string(&args[0])))
+        }
+        "num" => {
+            arity(name, args, 1, 1)?;
+            Ok(number(as_number(&args[0])?))
+        }
+        "abs" | "round" | "floor" | "ceil" => {
+            arity(name, args, 1, 1)?;
+            let n = as_number(&args[0])?;
+            Ok(number(match name {
+                "abs" => n.abs(),
+                "round" => n.round(),
+                "floor" => n.floor(),
+                _ => n.ceil(),
+            }))
+        }
+        "min" | "max" => {
+            arity(name, args, 1, usize::MAX)?;
+            let numbers = args
+                .iter()
+                .map(as_number)
+                .collect::<Result<Vec<f64>, ExpressionError>>()?;
+            Ok(number(if name == "min" {
+                numbers.into_iter().fold(f64::INFINITY, f64::min)
+            } else {
+                numbers.into_iter().fold(f64::NEG_INFINITY, f64::max)
+            }))
+        }
+        "coalesce" => Ok(args
+            .iter()
+            .find(|v| !v.is_null())
+            .cloned()
+            .unwrap_or(Value::Null)),
+        "json" => {
+            arity(name, args, 1, 1)?;
+            Ok(json!(args[0].to_string()))
+        }
+        "parse" => {
+            arity(name, args, 1, 1)?;
+            Ok(serde_json::from_str(&as_string(&args[0])).unwrap_or(Value::Null))
+        }
+        "pointer" => {
+            arity(name, args, 2, 2)?;
+            Ok(args[0]
+                .pointer(&as_string(&args[1]))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        _ => Err(ExpressionError::Unknown(name.into())),
+    }
+}
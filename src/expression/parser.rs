@@ -0,0 +1,310 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use super::eval::number;
+use super::ExpressionError;
+
+const MAX_LENGTH: usize = 4096;
+const MAX_DEPTH: usize = 64;
+const MAX_NODES: usize = 512;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Member(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    End,
+}
+
+const OPERATORS: [&str; 23] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", "[", "]",
+    ",", ".", "?", ":", "=",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExpressionError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f64>()
+                .map_err(|_| ExpressionError::Syntax(format!("Invalid number {text}")))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(ExpressionError::Syntax("Unterminated string".into())),
+                    Some(&q) if q == c => break,
+                    Some('\\') => {
+                        match chars.get(i + 1) {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(&e) => text.push(e),
+                            None => {
+                                return Err(ExpressionError::Syntax("Unterminated string".into()))
+                            }
+                        }
+                        i += 1;
+                    }
+                    Some(&o) => text.push(o),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push(Token::Str(text));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| ExpressionError::Syntax(format!("Unexpected character {c}")))?;
+            if *op == "=" {
+                return Err(ExpressionError::Syntax("Unexpected character =".into()));
+            }
+            i += op.len();
+            tokens.push(Token::Op(op));
+        }
+    }
+    tokens.push(Token::End);
+    Ok(tokens)
+}
+
+pub fn parse(source: &str) -> Result<Expr, ExpressionError> {
+    if source.len() > MAX_LENGTH {
+        return Err(ExpressionError::Syntax("Expression too long".into()));
+    }
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+        depth: 0,
+        nodes: 0,
+    };
+    let expr = parser.expression()?;
+    match parser.peek() {
+        Token::End => Ok(expr),
+        token => Err(ExpressionError::Syntax(format!(
+            "Unexpected token {token:?}"
+        ))),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+    nodes: usize,
+}
+
+fn binary_precedence(op: &str) -> Option<u8> {
+    match op {
+        "||" => Some(1),
+        "&&" => Some(2),
+        "==" | "!=" => Some(3),
+        "<" | "<=" | ">" | ">=" => Some(4),
+        "+" | "-" => Some(5),
+        "*" | "/" | "%" => Some(6),
+        _ => None,
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    // Bounds operator chains that do not increase depth
+    fn node(&mut self) -> Result<(), ExpressionError> {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES {
+            return Err(ExpressionError::Syntax("Expression too large".into()));
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ExpressionError> {
+        match self.next() {
+            Token::Op(o) if o == op => Ok(()),
+            token => Err(ExpressionError::Syntax(format!(
+                "Expected {op} found {token:?}"
+            ))),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, ExpressionError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExpressionError::Syntax("Expression too deep".into()));
+        }
+        let condition = self.binary(1)?;
+        let result = if self.peek() == &Token::Op("?") {
+            self.next();
+            let then = self.expression()?;
+            self.expect(":")?;
+            let otherwise = self.expression()?;
+            Expr::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise))
+        } else {
+            condition
+        };
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, ExpressionError> {
+        let mut left = self.unary()?;
+        while let Token::Op(op) = self.peek() {
+            let op = *op;
+            match binary_precedence(op) {
+                Some(precedence) if precedence >= min_precedence => {
+                    self.next();
+                    self.node()?;
+                    let right = self.binary(precedence + 1)?;
+                    left = Expr::Binary(op, Box::new(left), Box::new(right));
+                }
+                _ => break,
+            }
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.peek() {
+            Token::Op(op) if *op == "!" || *op == "-" => {
+                let op = *op;
+                self.next();
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(ExpressionError::Syntax("Expression too deep".into()));
+                }
+                let operand = self.unary()?;
+                self.depth -= 1;
+                Ok(Expr::Unary(op, Box::new(operand)))
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ExpressionError> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek() {
+                Token::Op(".") => {
+                    self.next();
+                    self.node()?;
+                    match self.next() {
+                        Token::Ident(name) => expr = Expr::Member(Box::new(expr), name),
+                        token => {
+                            return Err(ExpressionError::Syntax(format!(
+                                "Expected member name found {token:?}"
+                            )))
+                        }
+                    }
+                }
+                Token::Op("[") => {
+                    self.next();
+                    self.node()?;
+                    let index = self.expression()?;
+                    self.expect("]")?;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                _ => break,
+            }
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExpressionError> {
+        match self.next() {
+            Token::Number(n) => Ok(Expr::Literal(number(n))),
+            Token::Str(s) => Ok(Expr::Literal(json!(s))),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(json!(true))),
+                "false" => Ok(Expr::Literal(json!(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    if self.peek() == &Token::Op("(") {
+                        self.next();
+                        let mut args = vec![];
+                        if self.peek() != &Token::Op(")") {
+                            loop {
+                                args.push(self.expression()?);
+                                if self.peek() == &Token::Op(",") {
+                                    self.next();
+                                } else {
+                                    break;
+                                }
+                            }
+                        }
+                        self.expect(")")?;
+                        Ok(Expr::Call(name, args))
+                    } else {
+                        Ok(Expr::Variable(name))
+                    }
+                }
+            },
+            Token::Op("(") => {
+                let expr = self.expression()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            token => Err(ExpressionError::Syntax(format!(
+                "Unexpected token {token:?}"
+            ))),
+        }
+    }
+}
@@ -18,8 +18,10 @@
 //
 
 pub mod devices;
+pub mod expression;
 pub mod master;
 pub mod mqtt;
+pub mod plugins;
 pub mod rules;
 pub mod runtime;
 
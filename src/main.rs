@@ -20,21 +20,30 @@
 use config::Config;
 use myrulesiot::master::EngineStatus;
 use myrulesiot::master::FinalStatus;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
 
+use serde_json::{json, Value};
 use tokio::sync::mpsc;
 use tokio::{task, try_join};
 
 use myrulesiot::master::{
-    self, EngineAction, EngineResult, EngineState, MasterEngine, ReducerFunction,
+    self, Authorization, AuthorizationKey, EngineAction, EngineResult, EngineState,
+    FunctionOrigins, FunctionsHistory, MasterEngine, Persistence, ReducerFunction, ScriptLimits,
+    ScriptSources, StateFiles,
 };
 use myrulesiot::mqtt::{self, ConnectionValues, Subscription};
+use myrulesiot::plugins::{self, wasm::WasmLimits};
 use myrulesiot::rules;
 use myrulesiot::runtime;
 
 const FUNCTIONS_PATH: &str = "./engine_functions.json";
+const HISTORY_PATH: &str = "./engine_history.json";
+const SCRIPTS_PATH: &str = "./engine_scripts.json";
+const INFO_PATH: &str = "./engine_info.json";
+const JOURNAL_PATH: &str = "./engine_journal.json";
 const EXIT_PATH: &str = "./engine_exit";
 
 #[tokio::main]
@@ -54,6 +63,18 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Exit
     fs::remove_file(EXIT_PATH).unwrap_or_default();
 
+    // Saved state, completing a save interrupted by a crash
+    let state_files = StateFiles {
+        functions: FUNCTIONS_PATH.into(),
+        history: HISTORY_PATH.into(),
+        scripts: SCRIPTS_PATH.into(),
+        info: INFO_PATH.into(),
+        journal: JOURNAL_PATH.into(),
+    };
+    state_files
+        .recover()
+        .map_err(|error| format!("Cannot recover engine state files: {error}"))?;
+
     // Functions
     let functions = match Path::new(FUNCTIONS_PATH).try_exists() {
         Ok(true) => {
@@ -67,6 +88,95 @@ async fn main() -> Result<(), Box<dyn Error>> {
         _ => Vec::new(),
     };
 
+    // Functions history
+    let history = match Path::new(HISTORY_PATH).try_exists() {
+        Ok(true) => {
+            let f = fs::read(HISTORY_PATH).map_err(|error| {
+                format!("Cannot read functions history file {HISTORY_PATH}: {error}")
+            })?;
+            serde_json::from_slice::<FunctionsHistory>(&f).map_err(|error| {
+                format!("Cannot parse JSON functions history file {HISTORY_PATH}: {error}")
+            })?
+        }
+        _ => FunctionsHistory::default(),
+    };
+
+    // Scripts
+    let scripts = match Path::new(SCRIPTS_PATH).try_exists() {
+        Ok(true) => {
+            let f = fs::read(SCRIPTS_PATH)
+                .map_err(|error| format!("Cannot read scripts file {SCRIPTS_PATH}: {error}"))?;
+            serde_json::from_slice::<ScriptSources>(&f).map_err(|error| {
+                format!("Cannot parse JSON scripts file {SCRIPTS_PATH}: {error}")
+            })?
+        }
+        _ => ScriptSources::new(),
+    };
+
+    // Info, the state of the functions
+    let info = match Path::new(INFO_PATH).try_exists() {
+        Ok(true) => {
+            let f = fs::read(INFO_PATH)
+                .map_err(|error| format!("Cannot read info file {INFO_PATH}: {error}"))?;
+            serde_json::from_slice::<Value>(&f)
+                .map_err(|error| format!("Cannot parse JSON info file {INFO_PATH}: {error}"))?
+        }
+        _ => json!({}),
+    };
+
+    // Engine
+    let mut engine_functions = rules::distributed_engine_functions();
+    let mut function_origins = FunctionOrigins::new();
+    let plugins_directory = settings
+        .get_string("plugins.directory")
+        .unwrap_or_else(|_| String::from("./plugins"));
+    plugins::register_functions(
+        &mut engine_functions,
+        &mut function_origins,
+        plugins::wasm::load_wasm_plugins(
+            Path::new(&plugins_directory),
+            settings
+                .get::<WasmLimits>("plugins.wasm")
+                .unwrap_or_default(),
+        ),
+    );
+    // Native plugins run unrestricted, they are only loaded from an explicit directory
+    if let Ok(native_directory) = settings.get_string("plugins.native_directory") {
+        plugins::register_functions(
+            &mut engine_functions,
+            &mut function_origins,
+            plugins::native::load_native_plugins(Path::new(&native_directory)),
+        );
+    }
+    let mut engine = MasterEngine::new(prefix_id.clone(), engine_functions)
+        .with_function_origins(function_origins)
+        .with_parameter_specs(rules::distributed_parameter_specs());
+    if let Ok(history_limit) = settings.get_int("application.history_limit") {
+        let history_limit = usize::try_from(history_limit).map_err(|_| {
+            format!("Invalid application.history_limit {history_limit}, expected a positive number")
+        })?;
+        engine = engine.with_history_limit(history_limit);
+    }
+    let persist_millis = settings
+        .get_int("application.persist_millis")
+        .unwrap_or(10_000);
+    engine = engine.with_persistence(Persistence::new(state_files.clone(), persist_millis));
+    if let Ok(script_limits) = settings.get::<ScriptLimits>("scripts") {
+        engine = engine.with_script_limits(script_limits);
+    }
+    let keys = settings
+        .get::<Vec<AuthorizationKey>>("security.keys")
+        .unwrap_or(vec![]);
+    if keys.is_empty() {
+        log::warn!("No security keys configured. Commands are not authorized.");
+    } else {
+        let authorization = match settings.get_int("security.max_skew") {
+            Ok(max_skew) => Authorization::new_skew(keys, max_skew),
+            Err(_) => Authorization::new(keys),
+        };
+        engine = engine.with_authorization(authorization);
+    }
+
     let (sub_tx, sub_rx) = mpsc::channel::<EngineAction>(10);
     let (pub_tx, pub_rx) = mpsc::channel::<EngineResult>(10);
 
@@ -96,8 +206,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let enginetask = runtime::task_runtime_loop(
         pub_tx.clone(),
         sub_rx,
-        MasterEngine::new(prefix_id, rules::distributed_engine_functions()),
-        EngineState::new_functions(functions),
+        engine,
+        EngineState {
+            history,
+            scripts,
+            ..EngineState::new(info, functions)
+        },
     );
 
     std::mem::drop(sub_tx);
@@ -112,10 +226,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     )?;
     log::info!("Exiting myrulesiot...");
 
-    fs::write(
-        FUNCTIONS_PATH,
-        serde_json::to_vec_pretty(&state.functions).unwrap(),
-    )?;
+    state_files.save(&state)?;
 
     match state.engine_status {
         EngineStatus::FINAL(status, message) => {
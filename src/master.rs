@@ -24,5 +24,36 @@ pub use masterengine::{
     ReducerFunction, SliceFunction, SliceResult,
 };
 
+mod authorization;
+pub use authorization::{
+    sign, Authorization, AuthorizationError, AuthorizationKey, CommandOrigin, Permission,
+};
+
+mod batch;
+pub use batch::FunctionOperation;
+
+mod describe;
+pub use describe::{FunctionOrigin, FunctionOrigins};
+
+mod dryrun;
+pub use dryrun::{DryRun, DryRunAction};
+
+mod parameters;
+pub use parameters::{ParameterSpec, ParameterSpecs, ParameterType};
+
+mod persistence;
+pub use persistence::{write_atomic, Persistence, StateFiles};
+
+mod history;
+pub use history::{FunctionsHistory, FunctionsRevision};
+
+mod scripting;
+pub use scripting::{Script, ScriptError, ScriptLimits, ScriptRuntime, ScriptSources};
+
+mod shadow;
+pub use shadow::{ShadowLoad, ShadowReport, ShadowState};
+
+mod statekeys;
+
 mod timer;
 pub use timer::task_timer_loop;
@@ -0,0 +1,282 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use sha2::Sha256;
+use thiserror::Error;
+
+use super::EngineAction;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Read,
+    Edit,
+    Lifecycle,
+}
+
+impl Permission {
+    // Commands not listed here only require a valid key
+    pub fn for_command(command: &str) -> Option<Permission> {
+        match command {
+            "functions_getall" | "functions_history" | "functions_diff" | "functions_dryrun"
+            | "functions_describe" | "shadow_report" | "scripts_getall" => Some(Permission::Read),
+            "functions_push" | "functions_pop" | "functions_clear" | "functions_putall"
+            | "functions_batch" | "functions_rollback" | "shadow_putall" | "shadow_clear"
+            | "scripts_put" | "scripts_remove" => Some(Permission::Edit),
+            "exit" => Some(Permission::Lifecycle),
+            _ => None,
+        }
+    }
+}
+
+// A key authenticates commands with its bearer `token`, with HMAC signatures made with
+// its `secret`, or both. The secret never travels in a command so it is never accepted
+// as a token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorizationKey {
+    pub id: String,
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Error, Debug)]
+pub enum AuthorizationError {
+    #[error("Not an authorization envelope: {0}")]
+    Envelope(#[from] serde_json::Error),
+    #[error("Unknown key {0}")]
+    UnknownKey(String),
+    #[error("Missing token or signature")]
+    MissingCredentials,
+    #[error("Invalid token")]
+    InvalidToken,
+    #[error("Invalid signature")]
+    InvalidSignature,
+    #[error("Signature timestamp out of range")]
+    Expired,
+    #[error("Signature already used")]
+    Replayed,
+    #[error("Key {0} has not {1:?} permission")]
+    Forbidden(String, Permission),
+}
+
+// Who sent a command, recorded in the functions history
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CommandOrigin {
+    pub key: Option<String>,
+    pub author: Option<String>,
+    pub correlation: Option<String>,
+}
+
+impl CommandOrigin {
+    // Without authorization, a command payload may be an object with only
+    // `author`, `correlation` and `payload` keys to record who sent it.
+    // Any other payload is passed unchanged.
+    pub fn from_metadata(action: EngineAction) -> (EngineAction, CommandOrigin) {
+        let Ok(metadata) = serde_json::from_slice::<Metadata>(&action.payload) else {
+            return (action, CommandOrigin::default());
+        };
+        if metadata.author.is_none() && metadata.correlation.is_none() {
+            return (action, CommandOrigin::default());
+        }
+        let payload = envelope_payload(metadata.payload.as_deref());
+        (
+            EngineAction::new(action.topic, payload),
+            CommandOrigin {
+                key: None,
+                author: metadata.author,
+                correlation: metadata.correlation,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Metadata {
+    author: Option<String>,
+    correlation: Option<String>,
+    payload: Option<Box<RawValue>>,
+}
+
+// The payload of every command when authorization is enabled.
+// Either `token` or `signature` and `timestamp` must be present.
+// The payload is kept as received so signatures cover its exact JSON text.
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    key: String,
+    token: Option<String>,
+    signature: Option<String>,
+    timestamp: Option<i64>,
+    author: Option<String>,
+    correlation: Option<String>,
+    payload: Option<Box<RawValue>>,
+}
+
+pub struct Authorization {
+    keys: HashMap<String, AuthorizationKey>,
+    max_skew: i64,
+    // Signatures accepted within the skew window, rejected if received again
+    used_signatures: Mutex<HashMap<String, i64>>,
+}
+
+impl Authorization {
+    pub fn new(keys: Vec<AuthorizationKey>) -> Self {
+        Authorization::new_skew(keys, 300_000)
+    }
+
+    pub fn new_skew(keys: Vec<AuthorizationKey>, max_skew: i64) -> Self {
+        Authorization {
+            keys: keys
+                .into_iter()
+                .map(|mut key| {
+                    if key.token.is_some() && key.token == key.secret {
+                        log::error!("Key {} token is its secret, token disabled", key.id);
+                        key.token = None;
+                    }
+                    (key.id.clone(), key)
+                })
+                .collect(),
+            max_skew,
+            used_signatures: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Validates the command envelope and returns the action with the inner payload
+    pub fn authorize(
+        &self,
+        command: &str,
+        action: EngineAction,
+        timestamp: i64,
+    ) -> Result<(EngineAction, CommandOrigin), AuthorizationError> {
+        let envelope = serde_json::from_slice::<Envelope>(&action.payload)?;
+        let key = self
+            .keys
+            .get(&envelope.key)
+            .ok_or_else(|| AuthorizationError::UnknownKey(envelope.key.clone()))?;
+        let signed_payload = envelope
+            .payload
+            .as_ref()
+            .map_or(&b""[..], |raw| raw.get().as_bytes());
+        let payload = envelope_payload(envelope.payload.as_deref());
+
+        match (envelope.token, envelope.signature, envelope.timestamp) {
+            (Some(token), _, _) => {
+                let valid = key.token.as_ref().is_some_and(|key_token| {
+                    constant_time_eq(token.as_bytes(), key_token.as_bytes())
+                });
+                if !valid {
+                    return Err(AuthorizationError::InvalidToken);
+                }
+            }
+            (None, Some(signature), Some(signts)) => {
+                if (timestamp - signts).abs() > self.max_skew {
+                    return Err(AuthorizationError::Expired);
+                }
+                let secret = key
+                    .secret
+                    .as_ref()
+                    .ok_or(AuthorizationError::InvalidSignature)?;
+                let signature_bytes =
+                    hex::decode(&signature).map_err(|_| AuthorizationError::InvalidSignature)?;
+                signature_mac(secret, &action.topic, signts, signed_payload)
+                    .verify_slice(&signature_bytes)
+                    .map_err(|_| AuthorizationError::InvalidSignature)?;
+                self.check_replay(signature.to_lowercase(), signts, timestamp)?;
+            }
+            _ => return Err(AuthorizationError::MissingCredentials),
+        }
+
+        if let Some(permission) = Permission::for_command(command) {
+            if !key.permissions.contains(&permission) {
+                return Err(AuthorizationError::Forbidden(key.id.clone(), permission));
+            }
+        }
+
+        Ok((
+            EngineAction::new(action.topic, payload),
+            CommandOrigin {
+                key: Some(key.id.clone()),
+                author: envelope.author,
+                correlation: envelope.correlation,
+            },
+        ))
+    }
+
+    fn check_replay(
+        &self,
+        signature: String,
+        signts: i64,
+        timestamp: i64,
+    ) -> Result<(), AuthorizationError> {
+        let mut used = self.used_signatures.lock().unwrap();
+        // Signatures out of the skew window are already rejected as expired
+        used.retain(|_, used_ts| (timestamp - *used_ts).abs() <= self.max_skew);
+        match used.insert(signature, signts) {
+            Some(_) => Err(AuthorizationError::Replayed),
+            None => Ok(()),
+        }
+    }
+}
+
+// Signature is the hex encoded HMAC-SHA256 of "{topic}\n{timestamp}\n{payload}" where
+// payload is the JSON text of the envelope payload exactly as sent, quotes included
+// for strings, or empty without payload.
+pub fn sign(secret: &str, topic: &str, timestamp: i64, payload: &[u8]) -> String {
+    hex::encode(
+        signature_mac(secret, topic, timestamp, payload)
+            .finalize()
+            .into_bytes(),
+    )
+}
+
+fn signature_mac(secret: &str, topic: &str, timestamp: i64, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key");
+    mac.update(format!("{topic}\n{timestamp}\n").as_bytes());
+    mac.update(payload);
+    mac
+}
+
+// Strings are passed unquoted, other values as they were received
+fn envelope_payload(payload: Option<&RawValue>) -> Vec<u8> {
+    let Some(raw) = payload else {
+        return vec![];
+    };
+    match serde_json::from_str::<Value>(raw.get()) {
+        Ok(Value::Null) => vec![],
+        Ok(Value::String(s)) => s.into_bytes(),
+        _ => raw.get().as_bytes().to_vec(),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,164 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::ReducerFunction;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum FunctionOperation {
+    Add {
+        #[serde(default)]
+        index: Option<usize>,
+        function: ReducerFunction,
+    },
+    Remove {
+        index: usize,
+    },
+    Replace {
+        index: usize,
+        function: ReducerFunction,
+    },
+    Move {
+        from: usize,
+        to: usize,
+    },
+}
+
+// The functions list after a batch, with the previous position of each function
+pub struct Batch {
+    pub functions: Vec<ReducerFunction>,
+    pub positions: Vec<Option<usize>>,
+}
+
+// Applies all operations in order over a copy of the functions list.
+// Returns the new list only if every operation succeeds, and the result of each operation.
+pub fn apply_batch(
+    functions: &[ReducerFunction],
+    operations: Vec<FunctionOperation>,
+    exists: impl Fn(&str) -> bool,
+) -> (Option<Batch>, Vec<Value>) {
+    let mut batch: Vec<ReducerFunction> = functions.to_vec();
+    let mut positions: Vec<Option<usize>> = (0..functions.len()).map(Some).collect();
+    let mut success = true;
+
+    let results = operations
+        .into_iter()
+        .map(|operation| {
+            let result = apply_operation(&mut batch, &mut positions, operation, &exists);
+            success &= result["success"] == json!(true);
+            result
+        })
+        .collect();
+
+    let batch = Batch {
+        functions: batch,
+        positions,
+    };
+    (if success { Some(batch) } else { None }, results)
+}
+
+fn apply_operation(
+    batch: &mut Vec<ReducerFunction>,
+    positions: &mut Vec<Option<usize>>,
+    operation: FunctionOperation,
+    exists: &impl Fn(&str) -> bool,
+) -> Value {
+    let len = batch.len();
+    match operation {
+        FunctionOperation::Add { index, function } => {
+            let index = index.unwrap_or(len);
+            if index > len {
+                return operation_error("add", format!("Index {index} out of range"));
+            }
+            if !exists(function.name()) {
+                return operation_error("add", format!("Function not found: {}", function.name()));
+            }
+            let result = json!({
+                "op": "add",
+                "success": true,
+                "index": index,
+                "function": function.name(),
+            });
+            batch.insert(index, function);
+            positions.insert(index, None);
+            result
+        }
+        FunctionOperation::Remove { index } => {
+            if index >= len {
+                return operation_error("remove", format!("Index {index} out of range"));
+            }
+            let function = batch.remove(index);
+            positions.remove(index);
+            json!({
+                "op": "remove",
+                "success": true,
+                "index": index,
+                "function": function.name(),
+            })
+        }
+        FunctionOperation::Replace { index, function } => {
+            if index >= len {
+                return operation_error("replace", format!("Index {index} out of range"));
+            }
+            if !exists(function.name()) {
+                return operation_error(
+                    "replace",
+                    format!("Function not found: {}", function.name()),
+                );
+            }
+            let result = json!({
+                "op": "replace",
+                "success": true,
+                "index": index,
+                "function": function.name(),
+            });
+            batch[index] = function;
+            positions[index] = None;
+            result
+        }
+        FunctionOperation::Move { from, to } => {
+            if from >= len || to >= len {
+                return operation_error("move", format!("Index {from} or {to} out of range"));
+            }
+            let function = batch.remove(from);
+            let result = json!({
+                "op": "move",
+                "success": true,
+                "from": from,
+                "to": to,
+                "function": function.name(),
+            });
+            batch.insert(to, function);
+            let position = positions.remove(from);
+            positions.insert(to, position);
+            result
+        }
+    }
+}
+
+fn operation_error(op: &str, error: String) -> Value {
+    json!({
+        "op": op,
+        "success": false,
+        "error": error,
+    })
+}
@@ -0,0 +1,71 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::scripting::ScriptSources;
+use super::SliceFunction;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "origin", rename_all = "lowercase")]
+pub enum FunctionOrigin {
+    Builtin,
+    Script,
+    Wasm {
+        path: String,
+    },
+    Native {
+        path: String,
+        plugin: String,
+        version: String,
+    },
+}
+
+// Origin of every function not linked into the binary
+pub type FunctionOrigins = BTreeMap<String, FunctionOrigin>;
+
+pub fn describe_functions(
+    engine_functions: &HashMap<String, SliceFunction>,
+    origins: &FunctionOrigins,
+    scripts: &ScriptSources,
+) -> Vec<Value> {
+    let mut described: BTreeMap<&str, &FunctionOrigin> = engine_functions
+        .keys()
+        .map(|name| {
+            (
+                name.as_str(),
+                origins.get(name).unwrap_or(&FunctionOrigin::Builtin),
+            )
+        })
+        .collect();
+    for name in scripts.keys() {
+        described.insert(name.as_str(), &FunctionOrigin::Script);
+    }
+    described
+        .into_iter()
+        .map(|(name, origin)| {
+            let mut value = json!({ "name": name });
+            json_patch::merge(&mut value, &json!(origin));
+            value
+        })
+        .collect()
+}
@@ -0,0 +1,139 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::panic::{self, AssertUnwindSafe};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::parameters::check_engine_parameters;
+use super::scripting::ScriptSources;
+use super::{EngineAction, EngineMessage, MasterEngine, ReducerFunction};
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunAction {
+    pub topic: String,
+    #[serde(default)]
+    pub payload: Value,
+    pub timestamp: Option<i64>,
+}
+
+impl DryRunAction {
+    fn to_engineaction(&self) -> EngineAction {
+        match &self.payload {
+            Value::Null => EngineAction::new(self.topic.clone(), vec![]),
+            Value::String(s) => EngineAction::new(self.topic.clone(), s.clone().into_bytes()),
+            value => EngineAction::new_json(self.topic.clone(), value.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DryRun {
+    pub functions: Vec<ReducerFunction>,
+    #[serde(default)]
+    pub actions: Vec<DryRunAction>,
+}
+
+// Validates the candidate functions and their parameters and replays the actions over a
+// copy of info. The replay also catches failures of functions without specification.
+pub fn dry_run(
+    engine: &MasterEngine,
+    scripts: &ScriptSources,
+    info: &Value,
+    dryrun: DryRun,
+) -> Value {
+    let mut errors: Vec<Value> = vec![];
+    for (i, f) in dryrun.functions.iter().enumerate() {
+        let function_errors = if !engine.function_exists(f.name(), scripts) {
+            vec![format!("Function not found: {}", f.name())]
+        } else {
+            let mut function_errors = check_engine_parameters(f.parameters());
+            if let Some(spec) = engine.parameter_spec(f.name()) {
+                function_errors.extend(spec.check(f.parameters()));
+            }
+            function_errors
+        };
+        errors.extend(function_errors.into_iter().map(|error| {
+            json!({
+                "index": i,
+                "function": f.name(),
+                "error": error
+            })
+        }));
+    }
+
+    let mut results: Vec<Value> = vec![];
+    if errors.is_empty() {
+        let mut info = info.clone();
+        for action in dryrun.actions.iter() {
+            let timestamp = action
+                .timestamp
+                .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+            let engineaction = action.to_engineaction();
+            let mut candidate = info.clone();
+            let reduced = panic::catch_unwind(AssertUnwindSafe(|| {
+                engine.reduce_functions(
+                    &dryrun.functions,
+                    scripts,
+                    &mut candidate,
+                    &engineaction,
+                    timestamp,
+                )
+            }));
+            match reduced {
+                Ok(messages) => {
+                    info = candidate;
+                    results.push(json!({
+                        "topic": action.topic,
+                        "messages": messages.iter().map(message_to_json).collect::<Vec<Value>>(),
+                        "info": info,
+                    }));
+                }
+                Err(_) => {
+                    // The function that failed is the last one executed
+                    let index = candidate["_index"].as_u64().unwrap_or(0) as usize;
+                    errors.push(json!({
+                        "index": index,
+                        "function": dryrun.functions.get(index).map(|f| f.name()),
+                        "topic": action.topic,
+                        "error": "Function failed, check parameters"
+                    }));
+                    break;
+                }
+            }
+        }
+    }
+
+    json!({
+        "success": errors.is_empty(),
+        "errors": errors,
+        "results": results,
+    })
+}
+
+pub fn message_to_json(message: &EngineMessage) -> Value {
+    json!({
+        "topic": message.topic,
+        "payload": message
+            .payload_into_json()
+            .unwrap_or_else(|_| json!(String::from_utf8_lossy(&message.payload))),
+        "properties": message.properties,
+    })
+}
@@ -0,0 +1,105 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::authorization::CommandOrigin;
+use super::ReducerFunction;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionsRevision {
+    pub revision: u64,
+    pub timestamp: i64,
+    pub command: String,
+    #[serde(flatten)]
+    pub origin: CommandOrigin,
+    pub functions: Vec<ReducerFunction>,
+}
+
+impl FunctionsRevision {
+    pub fn summary(&self) -> Value {
+        json!({
+            "revision": self.revision,
+            "timestamp": self.timestamp,
+            "command": self.command,
+            "key": self.origin.key,
+            "author": self.origin.author,
+            "correlation": self.origin.correlation,
+            "count": self.functions.len(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FunctionsHistory {
+    next_revision: u64,
+    revisions: VecDeque<FunctionsRevision>,
+}
+
+impl FunctionsHistory {
+    pub fn revisions(&self) -> &VecDeque<FunctionsRevision> {
+        &self.revisions
+    }
+
+    pub fn get(&self, revision: u64) -> Option<&FunctionsRevision> {
+        self.revisions.iter().find(|r| r.revision == revision)
+    }
+
+    // Records the list of functions before and after a change, keeping at most `limit` revisions
+    pub fn record(
+        &mut self,
+        previous: &[ReducerFunction],
+        functions: &[ReducerFunction],
+        command: &str,
+        origin: &CommandOrigin,
+        timestamp: i64,
+        limit: usize,
+    ) -> u64 {
+        if self.revisions.is_empty() {
+            self.push(previous, "initial", &CommandOrigin::default(), timestamp);
+        }
+        let revision = self.push(functions, command, origin, timestamp);
+        while self.revisions.len() > limit.max(1) {
+            self.revisions.pop_front();
+        }
+        revision
+    }
+
+    fn push(
+        &mut self,
+        functions: &[ReducerFunction],
+        command: &str,
+        origin: &CommandOrigin,
+        timestamp: i64,
+    ) -> u64 {
+        let revision = self.next_revision;
+        self.next_revision += 1;
+        self.revisions.push_back(FunctionsRevision {
+            revision,
+            timestamp,
+            command: command.into(),
+            origin: origin.clone(),
+            functions: functions.to_vec(),
+        });
+        revision
+    }
+}
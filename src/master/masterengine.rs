@@ -19,11 +19,22 @@
 
 use crate::runtime::Engine;
 
+use super::authorization::{Authorization, CommandOrigin};
+use super::batch::{self, FunctionOperation};
+use super::describe::{self, FunctionOrigins};
+use super::dryrun::{self, DryRun};
+use super::history::FunctionsHistory;
+use super::parameters::{ParameterSpec, ParameterSpecs};
+use super::persistence::Persistence;
+use super::scripting::{Script, ScriptLimits, ScriptRuntime, ScriptSources};
+use super::shadow::{ShadowLoad, ShadowState};
+use super::statekeys;
+
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReducerFunction {
     name: String,
     #[serde(flatten)]
@@ -34,6 +45,19 @@ impl ReducerFunction {
     pub fn new(name: String, parameters: Value) -> Self {
         ReducerFunction { name, parameters }
     }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn parameters(&self) -> &Value {
+        &self.parameters
+    }
+    // Functions declaring _modes only run while the household mode is one of them
+    pub fn runs_in(&self, mode: Option<&str>) -> bool {
+        match (self.parameters["_modes"].as_array(), mode) {
+            (Some(modes), Some(mode)) => modes.iter().any(|m| m == mode),
+            _ => true,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -41,6 +65,12 @@ pub struct EngineState {
     pub info: Value,
     pub functions: Vec<ReducerFunction>,
     pub engine_status: EngineStatus,
+    #[serde(default)]
+    pub history: FunctionsHistory,
+    #[serde(default)]
+    pub shadow: Option<ShadowState>,
+    #[serde(default)]
+    pub scripts: ScriptSources,
 }
 
 impl Default for EngineState {
@@ -49,6 +79,9 @@ impl Default for EngineState {
             info: json!({}),
             functions: vec![],
             engine_status: EngineStatus::INIT,
+            history: FunctionsHistory::default(),
+            shadow: None,
+            scripts: ScriptSources::new(),
         }
     }
 }
@@ -59,6 +92,9 @@ impl EngineState {
             info,
             functions,
             engine_status: EngineStatus::INIT,
+            history: FunctionsHistory::default(),
+            shadow: None,
+            scripts: ScriptSources::new(),
         }
     }
     pub fn new_functions(functions: Vec<ReducerFunction>) -> Self {
@@ -66,6 +102,9 @@ impl EngineState {
             info: json!({}),
             functions,
             engine_status: EngineStatus::INIT,
+            history: FunctionsHistory::default(),
+            shadow: None,
+            scripts: ScriptSources::new(),
         }
     }
 }
@@ -74,16 +113,23 @@ impl EngineState {
 pub struct EngineAction {
     pub topic: String,
     pub payload: Vec<u8>,
+    // Retained messages are sent again by the broker on every connection
+    pub retain: bool,
 }
 
 impl EngineAction {
     pub fn new(topic: String, payload: Vec<u8>) -> Self {
-        EngineAction { topic, payload }
+        EngineAction {
+            topic,
+            payload,
+            retain: false,
+        }
     }
     pub fn new_json(topic: String, payload: Value) -> Self {
         EngineAction {
             topic,
             payload: payload.to_string().into_bytes(),
+            retain: false,
         }
     }
     pub fn matches(&self, filter: &str) -> bool {
@@ -92,6 +138,14 @@ impl EngineAction {
     pub fn matches_action(&self, filter: &str, payload: &[u8]) -> bool {
         self.topic.eq(filter) && payload.eq(&self.payload)
     }
+    // The action as passed to scripts and plugins
+    pub fn to_json(&self) -> Value {
+        json!({
+            "topic": self.topic,
+            "payload": String::from_utf8_lossy(&self.payload),
+            "json": serde_json::from_slice::<Value>(&self.payload).unwrap_or(Value::Null),
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -145,6 +199,8 @@ pub struct EngineResult {
 pub struct SliceResult {
     pub state: Value,
     pub messages: Vec<EngineMessage>,
+    // Failure reported by the engine in the system_error topic
+    pub error: Option<String>,
 }
 
 impl SliceResult {
@@ -152,22 +208,69 @@ impl SliceResult {
         SliceResult {
             state: json!({}),
             messages: vec![],
+            error: None,
         }
     }
     pub fn messages(messages: Vec<EngineMessage>) -> Self {
         SliceResult {
             state: json!({}),
             messages,
+            error: None,
         }
     }
     pub fn state(state: Value) -> Self {
         SliceResult {
             state,
             messages: vec![],
+            error: None,
         }
     }
     pub fn new(state: Value, messages: Vec<EngineMessage>) -> Self {
-        SliceResult { state, messages }
+        SliceResult {
+            state,
+            messages,
+            error: None,
+        }
+    }
+    pub fn error(error: String) -> Self {
+        SliceResult {
+            error: Some(error),
+            ..SliceResult::empty()
+        }
+    }
+    // Result returned by scripts and plugins, null or an object with optional `state` and `messages`
+    pub fn from_json(result: Value) -> Result<Self, String> {
+        if result.is_null() {
+            return Ok(SliceResult::empty());
+        }
+        if !result.is_object() {
+            return Err(format!("Not an object: {result}"));
+        }
+        let state = match &result["state"] {
+            Value::Null => json!({}),
+            state => state.clone(),
+        };
+        let messages = match &result["messages"] {
+            Value::Null => vec![],
+            Value::Array(messages) => messages
+                .iter()
+                .map(|m| {
+                    let topic = m["topic"]
+                        .as_str()
+                        .ok_or_else(|| format!("Message without topic: {m}"))?;
+                    let mut message = match &m["payload"] {
+                        Value::String(s) => {
+                            EngineMessage::new(topic.into(), s.clone().into_bytes())
+                        }
+                        payload => EngineMessage::new_json(topic.into(), payload),
+                    };
+                    message.properties = m["properties"].clone();
+                    Ok(message)
+                })
+                .collect::<Result<Vec<EngineMessage>, String>>()?,
+            other => return Err(format!("Messages not a list: {other}")),
+        };
+        Ok(SliceResult::new(state, messages))
     }
 }
 
@@ -189,6 +292,12 @@ pub enum EngineStatus {
 pub struct MasterEngine {
     prefix_id: String,
     engine_functions: HashMap<String, SliceFunction>,
+    origins: FunctionOrigins,
+    authorization: Option<Authorization>,
+    history_limit: usize,
+    script_runtime: ScriptRuntime,
+    persistence: Option<Persistence>,
+    parameter_specs: ParameterSpecs,
 }
 
 impl MasterEngine {
@@ -196,19 +305,217 @@ impl MasterEngine {
         Self {
             prefix_id,
             engine_functions,
+            origins: FunctionOrigins::new(),
+            authorization: None,
+            history_limit: 20,
+            script_runtime: ScriptRuntime::new(ScriptLimits::default()),
+            persistence: None,
+            parameter_specs: ParameterSpecs::new(),
         }
     }
+
+    pub fn with_authorization(mut self, authorization: Authorization) -> Self {
+        self.authorization = Some(authorization);
+        self
+    }
+
+    pub fn with_function_origins(mut self, origins: FunctionOrigins) -> Self {
+        self.origins = origins;
+        self
+    }
+
+    pub fn with_history_limit(mut self, history_limit: usize) -> Self {
+        self.history_limit = history_limit;
+        self
+    }
+
+    pub fn with_script_limits(mut self, limits: ScriptLimits) -> Self {
+        self.script_runtime = ScriptRuntime::new(limits);
+        self
+    }
+
+    pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    pub fn with_parameter_specs(mut self, parameter_specs: ParameterSpecs) -> Self {
+        self.parameter_specs = parameter_specs;
+        self
+    }
+
+    // Functions without specification, like plugins and scripts, are not checked
+    pub fn parameter_spec(&self, name: &str) -> Option<&ParameterSpec> {
+        self.parameter_specs.get(name)
+    }
+
+    pub fn engine_functions(&self) -> &HashMap<String, SliceFunction> {
+        &self.engine_functions
+    }
+
+    pub fn function_exists(&self, name: &str, scripts: &ScriptSources) -> bool {
+        self.engine_functions.contains_key(name) || scripts.contains_key(name)
+    }
+
+    // Executes the list of functions over info and returns the resulting messages
+    pub fn reduce_functions(
+        &self,
+        functions: &[ReducerFunction],
+        scripts: &ScriptSources,
+        info: &mut Value,
+        action: &EngineAction,
+        timestamp: i64,
+    ) -> Vec<EngineMessage> {
+        let mut messages = Vec::<EngineMessage>::new();
+
+        log::debug!("executing {} functions)", functions.len());
+        if let Value::Object(obj) = info {
+            obj.insert("_timestamp".into(), json!(timestamp));
+        }
+        for (i, fun) in functions.iter().enumerate() {
+            if !fun.runs_in(info["presence"]["mode"].as_str()) {
+                log::debug!("skipping {}-{} not in mode", i, fun.name);
+                continue;
+            }
+            log::debug!("executing {}-{}({})", i, fun.name, fun.parameters);
+
+            if let Value::Object(obj) = info {
+                obj.insert("_index".into(), json!(i));
+            }
+
+            let mut state = Value::Null;
+            let func = self.engine_functions.get(&fun.name);
+            match func {
+                Some(f) => {
+                    json_patch::merge(info, &fun.parameters);
+                    let mut result = f(info, action);
+                    json_patch::merge(info, &result.state);
+                    messages.append(&mut result.messages);
+                    if let Some(error) = result.error {
+                        messages.push(EngineMessage::new(
+                            format!("{}/notify/system_error", self.prefix_id),
+                            format!("Function {} failed: {}", &fun.name, error).into(),
+                        ))
+                    }
+                    state = result.state;
+                }
+                None => match scripts.get(&fun.name) {
+                    Some(source) => {
+                        json_patch::merge(info, &fun.parameters);
+                        match self.script_runtime.run(&fun.name, source, info, action) {
+                            Ok(mut result) => {
+                                json_patch::merge(info, &result.state);
+                                messages.append(&mut result.messages);
+                                state = result.state;
+                            }
+                            Err(error) => {
+                                log::warn!("Script {} failed: {}", fun.name, error);
+                                messages.push(EngineMessage::new(
+                                    format!("{}/notify/system_error", self.prefix_id),
+                                    format!("Script {} failed: {}", &fun.name, error).into(),
+                                ))
+                            }
+                        }
+                    }
+                    None => {
+                        log::warn!("Function not found: {}", fun.name);
+                        messages.push(EngineMessage::new(
+                            format!("{}/notify/system_error", self.prefix_id),
+                            format!("Function not found: {}", &fun.name).into(),
+                        ))
+                    }
+                },
+            }
+
+            // Non persistent parameters only apply to the function that declares them, so
+            // optional parameters do not leak into the next functions. Keys set by the
+            // function result, like _start, are kept to chain the next functions until the
+            // end of the reduce.
+            if let (Value::Object(obj), Value::Object(parameters)) = (&mut *info, &fun.parameters) {
+                for key in parameters
+                    .keys()
+                    .filter(|key| key.starts_with('_') && state.get(key.as_str()).is_none())
+                {
+                    obj.remove(key);
+                }
+            }
+        }
+        // Removes all non persitable keys
+        if let Value::Object(obj) = info {
+            obj.retain(|k: &String, _v: &mut Value| !k.starts_with("_"));
+        }
+
+        messages
+    }
+
+    fn authorize_command(
+        &self,
+        action: EngineAction,
+    ) -> Result<(EngineAction, CommandOrigin), EngineMessage> {
+        let prefix_id = &self.prefix_id;
+        let Some(command) = action
+            .topic
+            .strip_prefix(&format!("{prefix_id}/command/"))
+            .map(String::from)
+        else {
+            return Ok((action, CommandOrigin::default()));
+        };
+        let Some(authorization) = &self.authorization else {
+            return Ok(CommandOrigin::from_metadata(action));
+        };
+
+        authorization
+            .authorize(&command, action, chrono::Utc::now().timestamp_millis())
+            .map_err(|error| {
+                log::warn!("{command}: Command rejected. {error}");
+                EngineMessage::new_json(
+                    format!("{prefix_id}/notify/system_error"),
+                    &json!({
+                      "command" : command,
+                      "error" : error.to_string()
+                    }),
+                )
+            })
+    }
 }
 
 impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
     fn reduce(&self, state: EngineState, action: EngineAction) -> (EngineState, EngineResult) {
+        let (action, origin) = match self.authorize_command(action) {
+            Ok(authorized) => authorized,
+            Err(message) => {
+                return (
+                    state,
+                    EngineResult {
+                        messages: vec![message],
+                    },
+                )
+            }
+        };
+
         let mut messages = Vec::<EngineMessage>::new();
         let mut info = state.info;
         let mut functions = state.functions;
+        let mut history = state.history;
+        let mut shadow = state.shadow;
+        let mut scripts = state.scripts;
         let mut engine_status: EngineStatus = EngineStatus::RUNNING;
 
         let prefix_id = &self.prefix_id;
 
+        // Functions before the command, to record in history if edited
+        let previous = if action
+            .topic
+            .starts_with(&format!("{prefix_id}/command/functions_"))
+        {
+            Some(functions.clone())
+        } else {
+            None
+        };
+        let mut edited: Option<&str> = None;
+        // Previous position of each function if known, otherwise matched after the command
+        let mut positions: Option<Vec<Option<usize>>> = None;
+
         if action.matches(&format!("{prefix_id}/command/functions_push")) {
             match serde_json::from_slice::<ReducerFunction>(&action.payload) {
                 Ok(f) => {
@@ -220,6 +527,7 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
                         }),
                     ));
                     functions.push(f);
+                    edited = Some("functions_push");
                 }
                 Err(error) => {
                     log::warn!("functions_push: Not a ReducerFunction.");
@@ -234,6 +542,7 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
             }
         } else if action.matches(&format!("{}/command/functions_pop", self.prefix_id)) {
             let f = functions.pop();
+            edited = Some("functions_pop");
             messages.push(EngineMessage::new_json(
                 format!("{}/notify/functions_pop", self.prefix_id),
                 &json!({
@@ -243,6 +552,7 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
             ));
         } else if action.matches(&format!("{}/command/functions_clear", self.prefix_id)) {
             functions.clear();
+            edited = Some("functions_clear");
             messages.push(EngineMessage::new_json(
                 format!("{}/notify/functions_clear", self.prefix_id),
                 &json!({
@@ -253,6 +563,7 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
             match serde_json::from_slice(&action.payload) {
                 Ok(fns) => {
                     functions = fns;
+                    edited = Some("functions_putall");
                     messages.push(EngineMessage::new_json(
                         format!("{}/notify/functions_putall", self.prefix_id),
                         &json!({
@@ -271,11 +582,120 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
                     ))
                 }
             }
+        } else if action.matches(&format!("{prefix_id}/command/functions_batch")) {
+            match serde_json::from_slice::<Vec<FunctionOperation>>(&action.payload) {
+                Ok(operations) => {
+                    let (batch, results) = batch::apply_batch(&functions, operations, |name| {
+                        self.function_exists(name, &scripts)
+                    });
+                    let success = batch.is_some();
+                    if let Some(batch) = batch {
+                        functions = batch.functions;
+                        positions = Some(batch.positions);
+                        edited = Some("functions_batch");
+                    }
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/functions_batch"),
+                        &json!({
+                          "success" : success,
+                          "results" : results
+                        }),
+                    ));
+                }
+                Err(error) => {
+                    log::warn!("functions_batch: Not a list of operations.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "functions_batch",
+                          "error" : error.to_string()
+                        }),
+                    ))
+                }
+            }
+        } else if action.matches(&format!("{prefix_id}/command/functions_history")) {
+            let revisions: Vec<Value> = history.revisions().iter().map(|r| r.summary()).collect();
+            messages.push(EngineMessage::new_json(
+                format!("{prefix_id}/notify/functions_history"),
+                &revisions,
+            ));
+        } else if action.matches(&format!("{prefix_id}/command/functions_diff")) {
+            let payload = serde_json::from_slice::<Value>(&action.payload).unwrap_or(json!(null));
+            let from = payload["from"].as_u64().and_then(|r| history.get(r));
+            let to = payload["to"].as_u64().and_then(|r| history.get(r));
+            match (from, to) {
+                (Some(from), Some(to)) => {
+                    let diff = json_patch::diff(
+                        &serde_json::to_value(&from.functions).unwrap(),
+                        &serde_json::to_value(&to.functions).unwrap(),
+                    );
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/functions_diff"),
+                        &json!({
+                          "from" : from.revision,
+                          "to" : to.revision,
+                          "diff" : diff
+                        }),
+                    ));
+                }
+                _ => {
+                    log::warn!("functions_diff: Revision not found.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "functions_diff",
+                          "error" : format!("Revision not found: {payload}")
+                        }),
+                    ))
+                }
+            }
+        } else if action.matches(&format!("{prefix_id}/command/functions_rollback")) {
+            let payload = serde_json::from_slice::<Value>(&action.payload).unwrap_or(json!(null));
+            match payload["revision"].as_u64().and_then(|r| history.get(r)) {
+                Some(revision) => {
+                    functions = revision.functions.clone();
+                    edited = Some("functions_rollback");
+                }
+                None => {
+                    log::warn!("functions_rollback: Revision not found.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "functions_rollback",
+                          "error" : format!("Revision not found: {payload}")
+                        }),
+                    ))
+                }
+            }
+        } else if action.matches(&format!("{prefix_id}/command/functions_dryrun")) {
+            match serde_json::from_slice::<DryRun>(&action.payload) {
+                Ok(dryrun) => {
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/functions_dryrun"),
+                        &dryrun::dry_run(self, &scripts, &info, dryrun),
+                    ));
+                }
+                Err(error) => {
+                    log::warn!("functions_dryrun: Not a list of functions and actions.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "functions_dryrun",
+                          "error" : error.to_string()
+                        }),
+                    ))
+                }
+            }
         } else if action.matches(&format!("{}/command/functions_getall", self.prefix_id)) {
             messages.push(EngineMessage::new_json(
                 format!("{prefix_id}/notify/functions_getall"),
                 &functions,
             ));
+        } else if action.matches(&format!("{prefix_id}/command/functions_describe")) {
+            messages.push(EngineMessage::new_json(
+                format!("{prefix_id}/notify/functions_describe"),
+                &describe::describe_functions(&self.engine_functions, &self.origins, &scripts),
+            ));
         } else if action.matches(&format!("{prefix_id}/command/exit")) {
             engine_status = EngineStatus::FINAL(
                 FinalStatus::NORMAL,
@@ -286,39 +706,170 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
                 String::from_utf8(action.payload).unwrap_or_else(|utferror| utferror.to_string());
             log::error!("System Master Engine error. Received error {final_message:?}");
             engine_status = EngineStatus::FINAL(FinalStatus::ERROR, final_message);
-        } else {
-            log::debug!("executing {} functions)", functions.len());
-            if let Value::Object(obj) = &mut info {
-                let utc = chrono::Utc::now().timestamp_millis();
-                obj.insert("_timestamp".into(), json!(utc));
+        } else if action.matches(&format!("{prefix_id}/command/scripts_put")) {
+            let script = serde_json::from_slice::<Script>(&action.payload)
+                .map_err(|error| error.to_string())
+                .and_then(|script| {
+                    if self.engine_functions.contains_key(&script.name) {
+                        return Err(format!("Function already exists: {}", script.name));
+                    }
+                    self.script_runtime
+                        .compile(&script.source)
+                        .map_err(|error| error.to_string())?;
+                    Ok(script)
+                });
+            match script {
+                Ok(script) => {
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/scripts_put"),
+                        &json!({
+                          "success" : true,
+                          "name" : script.name
+                        }),
+                    ));
+                    scripts.insert(script.name, script.source);
+                }
+                Err(error) => {
+                    log::warn!("scripts_put: Not a valid script.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "scripts_put",
+                          "error" : error
+                        }),
+                    ))
+                }
             }
-            for (i, fun) in functions.iter().enumerate() {
-                log::debug!("executing {}-{}({})", i, fun.name, fun.parameters);
-
-                if let Value::Object(obj) = &mut info {
-                    obj.insert("_index".into(), json!(i));
+        } else if action.matches(&format!("{prefix_id}/command/scripts_remove")) {
+            let name = String::from_utf8_lossy(&action.payload).to_string();
+            // Scripts still used by the live or the shadow functions are kept
+            let used = functions
+                .iter()
+                .chain(shadow.iter().flat_map(|shadow| shadow.functions.iter()))
+                .filter(|f| f.name() == name)
+                .count();
+            if used == 0 {
+                let removed = scripts.remove(&name).is_some();
+                self.script_runtime.evict(&name);
+                messages.push(EngineMessage::new_json(
+                    format!("{prefix_id}/notify/scripts_remove"),
+                    &json!({
+                      "success" : removed,
+                      "name" : name
+                    }),
+                ));
+            } else {
+                log::warn!("scripts_remove: Script {name} still in use.");
+                messages.push(EngineMessage::new_json(
+                    format!("{prefix_id}/notify/system_error"),
+                    &json!({
+                      "command" : "scripts_remove",
+                      "error" : format!("Script {name} used by {used} functions")
+                    }),
+                ));
+            }
+        } else if action.matches(&format!("{prefix_id}/command/scripts_getall")) {
+            let all: Vec<Script> = scripts
+                .iter()
+                .map(|(name, source)| Script {
+                    name: name.clone(),
+                    source: source.clone(),
+                })
+                .collect();
+            messages.push(EngineMessage::new_json(
+                format!("{prefix_id}/notify/scripts_getall"),
+                &all,
+            ));
+        } else if action.matches(&format!("{prefix_id}/command/shadow_putall")) {
+            match serde_json::from_slice::<ShadowLoad>(&action.payload) {
+                Ok(load) => {
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/shadow_putall"),
+                        &json!({
+                          "success" : true,
+                          "count" : load.functions.len()
+                        }),
+                    ));
+                    shadow = Some(ShadowState::new(
+                        load,
+                        info.clone(),
+                        chrono::Utc::now().timestamp_millis(),
+                    ));
                 }
-
-                let func = self.engine_functions.get(&fun.name);
-                match func {
-                    Some(f) => {
-                        json_patch::merge(&mut info, &fun.parameters);
-                        let mut result = f(&mut info, &action);
-                        json_patch::merge(&mut info, &result.state);
-                        messages.append(&mut result.messages);
-                    }
-                    None => {
-                        log::warn!("Function not found: {}", fun.name);
-                        messages.push(EngineMessage::new(
-                            format!("{}/notify/system_error", self.prefix_id),
-                            format!("Function not found: {}", &fun.name).into(),
-                        ))
-                    }
+                Err(error) => {
+                    log::warn!("shadow_putall: Not a list of ReducerFunction.");
+                    messages.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/system_error"),
+                        &json!({
+                          "command" : "shadow_putall",
+                          "error" : error.to_string()
+                        }),
+                    ))
+                }
+            }
+        } else if action.matches(&format!("{prefix_id}/command/shadow_clear")) {
+            let report = shadow
+                .take()
+                .map(|mut s| s.take_report(chrono::Utc::now().timestamp_millis()));
+            messages.push(EngineMessage::new_json(
+                format!("{prefix_id}/notify/shadow_clear"),
+                &json!({
+                  "success" : true,
+                  "report" : report
+                }),
+            ));
+        } else if action.matches(&format!("{prefix_id}/command/shadow_report")) {
+            let report = shadow
+                .as_mut()
+                .map(|s| s.take_report(chrono::Utc::now().timestamp_millis()));
+            messages.push(EngineMessage::new_json(
+                format!("{prefix_id}/notify/shadow_report"),
+                &report,
+            ));
+        } else {
+            let timestamp = chrono::Utc::now().timestamp_millis();
+            let mut live =
+                self.reduce_functions(&functions, &scripts, &mut info, &action, timestamp);
+            if let Some(shadow) = &mut shadow {
+                let shadow_messages = self.reduce_functions(
+                    &shadow.functions,
+                    &scripts,
+                    &mut shadow.info,
+                    &action,
+                    timestamp,
+                );
+                shadow.compare(&action, &live, &shadow_messages, timestamp);
+                live.append(&mut shadow.output(prefix_id, shadow_messages));
+                if shadow.is_report_due(timestamp) {
+                    live.push(EngineMessage::new_json(
+                        format!("{prefix_id}/notify/shadow_report"),
+                        &shadow.take_report(timestamp),
+                    ));
                 }
             }
-            // Removes all non persitable keys
-            if let Value::Object(obj) = &mut info {
-                obj.retain(|k: &String, _v: &mut Value| !k.starts_with("_"));
+            messages.append(&mut live);
+        }
+
+        if let (Some(previous), Some(command)) = (previous, edited) {
+            let positions =
+                positions.unwrap_or_else(|| statekeys::matching_positions(&previous, &functions));
+            statekeys::move_state(&mut info, previous.len(), &positions);
+            let revision = history.record(
+                &previous,
+                &functions,
+                command,
+                &origin,
+                chrono::Utc::now().timestamp_millis(),
+                self.history_limit,
+            );
+            if command == "functions_rollback" {
+                messages.push(EngineMessage::new_json(
+                    format!("{prefix_id}/notify/functions_rollback"),
+                    &json!({
+                      "success" : true,
+                      "revision" : revision
+                    }),
+                ));
             }
         }
 
@@ -327,6 +878,9 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
                 engine_status,
                 info,
                 functions,
+                history,
+                shadow,
+                scripts,
             },
             EngineResult { messages },
         )
@@ -334,4 +888,10 @@ impl Engine<EngineAction, EngineResult, EngineState> for MasterEngine {
     fn is_final(&self, state: &EngineState) -> bool {
         matches!(state.engine_status, EngineStatus::FINAL(..))
     }
+
+    fn persist(&self, state: &EngineState) {
+        if let Some(persistence) = &self.persistence {
+            persistence.persist(state, chrono::Utc::now().timestamp_millis());
+        }
+    }
 }
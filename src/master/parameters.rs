@@ -0,0 +1,102 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+    Object,
+    Array,
+    Any,
+}
+
+impl ParameterType {
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            ParameterType::String => value.is_string(),
+            ParameterType::Number => value.is_number(),
+            ParameterType::Integer => value.is_i64() || value.is_u64(),
+            ParameterType::Boolean => value.is_boolean(),
+            ParameterType::Object => value.is_object(),
+            ParameterType::Array => value.is_array(),
+            ParameterType::Any => !value.is_null(),
+        }
+    }
+}
+
+// The parameters a function expects
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParameterSpec {
+    #[serde(default)]
+    pub required: BTreeMap<String, ParameterType>,
+    #[serde(default)]
+    pub optional: BTreeMap<String, ParameterType>,
+}
+
+pub type ParameterSpecs = HashMap<String, ParameterSpec>;
+
+impl ParameterSpec {
+    pub fn required(mut self, name: &str, parameter_type: ParameterType) -> Self {
+        self.required.insert(name.into(), parameter_type);
+        self
+    }
+
+    pub fn optional(mut self, name: &str, parameter_type: ParameterType) -> Self {
+        self.optional.insert(name.into(), parameter_type);
+        self
+    }
+
+    // Returns the errors of the parameters, missing required ones and wrong types
+    pub fn check(&self, parameters: &Value) -> Vec<String> {
+        let mut errors = vec![];
+        for (name, parameter_type) in &self.required {
+            match &parameters[name] {
+                Value::Null => errors.push(format!("Missing parameter {name}")),
+                value if !parameter_type.accepts(value) => {
+                    errors.push(format!("Parameter {name} must be {parameter_type:?}"))
+                }
+                _ => {}
+            }
+        }
+        for (name, parameter_type) in &self.optional {
+            let value = &parameters[name];
+            if !value.is_null() && !parameter_type.accepts(value) {
+                errors.push(format!("Parameter {name} must be {parameter_type:?}"));
+            }
+        }
+        errors
+    }
+}
+
+// Parameters the engine reads for every function
+pub fn check_engine_parameters(parameters: &Value) -> Vec<String> {
+    match &parameters["_modes"] {
+        Value::Null => vec![],
+        Value::Array(modes) if modes.iter().all(Value::is_string) => vec![],
+        _ => vec![String::from("Parameter _modes must be Array of String")],
+    }
+}
@@ -0,0 +1,179 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::EngineState;
+
+// Writes to a temporary file renamed over the target, a crash never leaves it truncated
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let temporary = temporary(path);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+fn temporary(path: &Path) -> PathBuf {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    temporary.into()
+}
+
+// The files where the engine state is saved. They are written together through
+// the journal, so after a crash they are either all the previous or all the new ones.
+#[derive(Debug, Clone)]
+pub struct StateFiles {
+    pub functions: PathBuf,
+    pub history: PathBuf,
+    pub scripts: PathBuf,
+    pub info: PathBuf,
+    pub journal: PathBuf,
+}
+
+impl StateFiles {
+    fn contents(&self, state: &EngineState) -> Vec<(&Path, Vec<u8>)> {
+        vec![
+            (
+                self.functions.as_path(),
+                serde_json::to_vec_pretty(&state.functions).unwrap(),
+            ),
+            (
+                self.history.as_path(),
+                serde_json::to_vec(&state.history).unwrap(),
+            ),
+            (
+                self.scripts.as_path(),
+                serde_json::to_vec_pretty(&state.scripts).unwrap(),
+            ),
+            (
+                self.info.as_path(),
+                serde_json::to_vec(&state.info).unwrap(),
+            ),
+        ]
+    }
+
+    // Writes the temporary files, then the journal listing them, which commits the
+    // change, and finally renames them over the targets
+    fn write_all(&self, files: &[(&Path, Vec<u8>)]) -> io::Result<()> {
+        for (path, contents) in files {
+            let mut file = fs::File::create(temporary(path))?;
+            file.write_all(contents)?;
+            file.sync_all()?;
+        }
+        let paths: Vec<&Path> = files.iter().map(|(path, _)| *path).collect();
+        write_atomic(&self.journal, &serde_json::to_vec(&paths)?)?;
+        self.complete(&paths)
+    }
+
+    fn complete(&self, paths: &[&Path]) -> io::Result<()> {
+        for path in paths {
+            let temporary = temporary(path);
+            if temporary.exists() {
+                fs::rename(temporary, path)?;
+            }
+        }
+        fs::remove_file(&self.journal)
+    }
+
+    pub fn save(&self, state: &EngineState) -> io::Result<()> {
+        self.write_all(&self.contents(state))
+    }
+
+    // Completes the save interrupted by a crash, or discards it if the journal was not
+    // written. Called before loading the files.
+    pub fn recover(&self) -> io::Result<()> {
+        match fs::read(&self.journal) {
+            Ok(journal) => {
+                let paths: Vec<PathBuf> = serde_json::from_slice(&journal)?;
+                log::warn!("Completing interrupted save of {} files", paths.len());
+                self.complete(&paths.iter().map(PathBuf::as_path).collect::<Vec<&Path>>())
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                for path in [
+                    &self.functions,
+                    &self.history,
+                    &self.scripts,
+                    &self.info,
+                    &self.journal,
+                ] {
+                    match fs::remove_file(temporary(path)) {
+                        Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                        _ => {}
+                    }
+                }
+                Ok(())
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+struct Saved {
+    at: i64,
+    contents: HashMap<PathBuf, Vec<u8>>,
+}
+
+// Saves the engine state while running, every interval_millis at most and only the
+// files that changed since the last save
+pub struct Persistence {
+    files: StateFiles,
+    interval_millis: i64,
+    saved: Mutex<Saved>,
+}
+
+impl Persistence {
+    pub fn new(files: StateFiles, interval_millis: i64) -> Self {
+        Persistence {
+            files,
+            interval_millis,
+            saved: Mutex::new(Saved {
+                at: i64::MIN,
+                contents: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn persist(&self, state: &EngineState, timestamp: i64) {
+        let mut saved = self.saved.lock().unwrap();
+        if timestamp.saturating_sub(saved.at) < self.interval_millis {
+            return;
+        }
+        saved.at = timestamp;
+        let changed: Vec<(&Path, Vec<u8>)> = self
+            .files
+            .contents(state)
+            .into_iter()
+            .filter(|(path, contents)| saved.contents.get(*path) != Some(contents))
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        match self.files.write_all(&changed) {
+            Ok(()) => {
+                for (path, contents) in changed {
+                    saved.contents.insert(path.to_path_buf(), contents);
+                }
+            }
+            Err(error) => log::warn!("Cannot save engine state: {error}"),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use super::{EngineAction, SliceResult};
+
+// The entry point every script must define
+const SCRIPT_FUNCTION: &str = "slice";
+
+pub type ScriptSources = BTreeMap<String, String>;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Script {
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("Compilation error: {0}")]
+    Compile(String),
+    #[error("Script must define the function {SCRIPT_FUNCTION}(info, action)")]
+    MissingFunction,
+    #[error("Runtime error: {0}")]
+    Runtime(String),
+    #[error("Invalid result: {0}")]
+    Result(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    pub max_operations: u64,
+    pub max_millis: u64,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        ScriptLimits {
+            max_operations: 100_000,
+            max_millis: 50,
+        }
+    }
+}
+
+pub struct ScriptRuntime {
+    engine: Engine,
+    deadline: Arc<Mutex<Option<Instant>>>,
+    max_duration: Duration,
+    // Compiled scripts by name with the source they were compiled from
+    compiled: Mutex<HashMap<String, (String, Arc<AST>)>>,
+}
+
+impl ScriptRuntime {
+    pub fn new(limits: ScriptLimits) -> Self {
+        let deadline: Arc<Mutex<Option<Instant>>> = Arc::new(Mutex::new(None));
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(limits.max_operations)
+            .set_max_call_levels(32)
+            .set_max_expr_depths(64, 32)
+            .set_max_string_size(64 * 1024)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000)
+            .disable_symbol("eval")
+            .on_print(|text| log::info!("script: {text}"))
+            .on_debug(|text, _, _| log::debug!("script: {text}"));
+        let progress_deadline = deadline.clone();
+        engine.on_progress(move |_| match *progress_deadline.lock().unwrap() {
+            Some(d) if Instant::now() > d => Some(Dynamic::UNIT),
+            _ => None,
+        });
+        ScriptRuntime {
+            engine,
+            deadline,
+            max_duration: Duration::from_millis(limits.max_millis),
+            compiled: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn compile(&self, source: &str) -> Result<AST, ScriptError> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|error| ScriptError::Compile(error.to_string()))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == SCRIPT_FUNCTION && f.params.len() == 2)
+        {
+            return Err(ScriptError::MissingFunction);
+        }
+        Ok(ast)
+    }
+
+    // Compiles the script only the first time or when its source changed
+    fn compiled(&self, name: &str, source: &str) -> Result<Arc<AST>, ScriptError> {
+        let mut compiled = self.compiled.lock().unwrap();
+        if let Some((compiled_source, ast)) = compiled.get(name) {
+            if compiled_source == source {
+                return Ok(ast.clone());
+            }
+        }
+        let ast = Arc::new(self.compile(source)?);
+        compiled.insert(name.into(), (source.into(), ast.clone()));
+        Ok(ast)
+    }
+
+    // Drops the compiled script, returns whether it was compiled
+    pub fn evict(&self, name: &str) -> bool {
+        self.compiled.lock().unwrap().remove(name).is_some()
+    }
+
+    pub fn run(
+        &self,
+        name: &str,
+        source: &str,
+        info: &Value,
+        action: &EngineAction,
+    ) -> Result<SliceResult, ScriptError> {
+        let ast = self.compiled(name, source)?;
+        let to_dynamic = |value: &Value| {
+            rhai::serde::to_dynamic(value).map_err(|error| ScriptError::Runtime(error.to_string()))
+        };
+        let info = to_dynamic(info)?;
+        let action = to_dynamic(&action.to_json())?;
+
+        *self.deadline.lock().unwrap() = Some(Instant::now() + self.max_duration);
+        let result = self.engine.call_fn::<Dynamic>(
+            &mut Scope::new(),
+            &ast,
+            SCRIPT_FUNCTION,
+            (info, action),
+        );
+        *self.deadline.lock().unwrap() = None;
+
+        let result = result.map_err(|error| ScriptError::Runtime(error.to_string()))?;
+        let result: Value = rhai::serde::from_dynamic(&result)
+            .map_err(|error| ScriptError::Result(error.to_string()))?;
+        SliceResult::from_json(result).map_err(ScriptError::Result)
+    }
+}
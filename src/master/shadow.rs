@@ -0,0 +1,142 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::dryrun::message_to_json;
+use super::{EngineAction, EngineMessage, ReducerFunction};
+
+const MAX_DIVERGENCES: usize = 10;
+
+#[derive(Debug, Deserialize)]
+pub struct ShadowLoad {
+    pub functions: Vec<ReducerFunction>,
+    #[serde(default = "divert_default")]
+    pub divert: bool,
+    #[serde(default = "report_interval_default")]
+    pub report_interval: i64,
+}
+
+fn divert_default() -> bool {
+    true
+}
+fn report_interval_default() -> i64 {
+    60_000
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ShadowReport {
+    pub since: i64,
+    pub actions: u64,
+    pub diverged: u64,
+    pub divergences: Vec<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShadowState {
+    pub functions: Vec<ReducerFunction>,
+    pub info: Value,
+    pub divert: bool,
+    pub report_interval: i64,
+    pub report: ShadowReport,
+}
+
+impl ShadowState {
+    pub fn new(load: ShadowLoad, info: Value, timestamp: i64) -> Self {
+        ShadowState {
+            functions: load.functions,
+            info,
+            divert: load.divert,
+            report_interval: load.report_interval,
+            report: ShadowReport {
+                since: timestamp,
+                ..ShadowReport::default()
+            },
+        }
+    }
+
+    // Records whether the shadow functions produced different messages than the live ones
+    pub fn compare(
+        &mut self,
+        action: &EngineAction,
+        live: &[EngineMessage],
+        shadow: &[EngineMessage],
+        timestamp: i64,
+    ) {
+        self.report.actions += 1;
+        if sorted_messages(live) != sorted_messages(shadow) {
+            self.report.diverged += 1;
+            if self.report.divergences.len() < MAX_DIVERGENCES {
+                self.report.divergences.push(json!({
+                    "timestamp": timestamp,
+                    "topic": action.topic,
+                    "live": live.iter().map(message_to_json).collect::<Vec<Value>>(),
+                    "shadow": shadow.iter().map(message_to_json).collect::<Vec<Value>>(),
+                }));
+            }
+        }
+    }
+
+    // Messages to publish for the shadow output, diverted to the shadow topic or suppressed
+    pub fn output(&self, prefix_id: &str, shadow: Vec<EngineMessage>) -> Vec<EngineMessage> {
+        if !self.divert {
+            return vec![];
+        }
+        shadow
+            .into_iter()
+            .filter(|m| !m.topic.starts_with("SYSMR/"))
+            .map(|m| EngineMessage {
+                topic: format!("{prefix_id}/shadow/{}", m.topic),
+                ..m
+            })
+            .collect()
+    }
+
+    // Returns the report and starts a new one
+    pub fn take_report(&mut self, timestamp: i64) -> Value {
+        let report = std::mem::replace(
+            &mut self.report,
+            ShadowReport {
+                since: timestamp,
+                ..ShadowReport::default()
+            },
+        );
+        json!({
+            "since": report.since,
+            "until": timestamp,
+            "actions": report.actions,
+            "diverged": report.diverged,
+            "divergences": report.divergences,
+        })
+    }
+
+    pub fn is_report_due(&self, timestamp: i64) -> bool {
+        self.report_interval > 0 && timestamp >= self.report.since + self.report_interval
+    }
+}
+
+fn sorted_messages(messages: &[EngineMessage]) -> Vec<(&str, &[u8])> {
+    let mut sorted: Vec<(&str, &[u8])> = messages
+        .iter()
+        .map(|m| (m.topic.as_str(), m.payload.as_slice()))
+        .collect();
+    sorted.sort();
+    sorted
+}
@@ -0,0 +1,75 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::Value;
+
+use super::ReducerFunction;
+
+// For each function in the new list, the position it had in the previous list.
+// Functions are matched by name and parameters, in order.
+pub fn matching_positions(
+    previous: &[ReducerFunction],
+    functions: &[ReducerFunction],
+) -> Vec<Option<usize>> {
+    let mut used = vec![false; previous.len()];
+    functions
+        .iter()
+        .map(|f| {
+            let position = previous.iter().enumerate().position(|(i, p)| {
+                !used[i] && p.name() == f.name() && p.parameters() == f.parameters()
+            })?;
+            used[position] = true;
+            Some(position)
+        })
+        .collect()
+}
+
+// Function state is kept in info keys ending with the function position, "<prefix>_<index>".
+// Moves each key to the new position of its function and drops the keys of removed functions.
+pub fn move_state(info: &mut Value, previous_len: usize, positions: &[Option<usize>]) {
+    let unchanged =
+        positions.len() == previous_len && positions.iter().enumerate().all(|(i, p)| *p == Some(i));
+    let Some(object) = info.as_object_mut().filter(|_| !unchanged) else {
+        return;
+    };
+
+    let keys: Vec<(String, usize)> = object
+        .keys()
+        .filter_map(|key| Some((key.clone(), state_index(key, previous_len)?)))
+        .collect();
+    let mut moved = Vec::new();
+    for (key, index) in keys {
+        let value = object.remove(&key).unwrap();
+        if let Some(position) = positions.iter().position(|p| *p == Some(index)) {
+            let (prefix, _) = key.rsplit_once('_').unwrap();
+            moved.push((format!("{prefix}_{position}"), value));
+        }
+    }
+    object.extend(moved);
+}
+
+// The function index of a state key, if it is below the previous list length
+fn state_index(key: &str, previous_len: usize) -> Option<usize> {
+    if key.starts_with('_') {
+        return None;
+    }
+    let (prefix, index) = key.rsplit_once('_')?;
+    let parsed = index.parse::<usize>().ok()?;
+    (!prefix.is_empty() && parsed.to_string() == index && parsed < previous_len).then_some(parsed)
+}
@@ -73,7 +73,9 @@ pub struct Subscription {
 }
 
 fn to_engineaction(p: Publish) -> EngineAction {
-    EngineAction::new(p.topic, p.payload.into())
+    let mut action = EngineAction::new(p.topic, p.payload.into());
+    action.retain = p.retain;
+    action
 }
 
 #[derive(Error, Debug)]
@@ -185,7 +187,7 @@ pub async fn task_publication_loop(mut rx: mpsc::Receiver<EngineResult>, client:
                     elem.topic,
                     elem.properties["qos"]
                         .as_i64()
-                        .and_then(|i| to_qos(i))
+                        .and_then(to_qos)
                         .unwrap_or(QoS::AtLeastOnce),
                     elem.properties["retain"].as_bool().unwrap_or(false),
                     elem.payload,
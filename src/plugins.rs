@@ -0,0 +1,61 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::master::{FunctionOrigin, FunctionOrigins, SliceFunction};
+
+pub mod native;
+pub mod wasm;
+
+// The functions exported by a loaded plugin
+pub struct PluginFunctions {
+    pub origin: FunctionOrigin,
+    pub functions: Vec<(String, SliceFunction)>,
+}
+
+// Adds plugin functions to the engine functions. Names already registered are skipped.
+pub fn register_functions(
+    engine_functions: &mut HashMap<String, SliceFunction>,
+    origins: &mut FunctionOrigins,
+    plugins: Vec<PluginFunctions>,
+) -> Vec<String> {
+    let mut rejected = vec![];
+    for plugin in plugins {
+        for (name, function) in plugin.functions {
+            match engine_functions.entry(name) {
+                Entry::Occupied(entry) => {
+                    log::warn!(
+                        "Plugin function {} already registered as {:?}, skipped from {:?}.",
+                        entry.key(),
+                        origins.get(entry.key()).unwrap_or(&FunctionOrigin::Builtin),
+                        plugin.origin
+                    );
+                    rejected.push(entry.key().clone());
+                }
+                Entry::Vacant(entry) => {
+                    origins.insert(entry.key().clone(), plugin.origin.clone());
+                    entry.insert(function);
+                }
+            }
+        }
+    }
+    rejected
+}
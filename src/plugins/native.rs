@@ -0,0 +1,236 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+// Native plugin ABI. A shared library exports these C functions:
+// - `myrulesiot_plugin_manifest() -> *const c_char` a static JSON PluginManifest.
+// - `myrulesiot_plugin_call(function: *const c_char, input: *const c_char) -> *mut c_char`
+//   runs a function with the JSON {"info": ..., "action": ...} and returns the JSON
+//   SliceResult, or null on failure.
+// - `myrulesiot_plugin_free(result: *mut c_char)` releases a result of the call function.
+
+use std::env::consts::DLL_EXTENSION;
+use std::ffi::{c_char, CStr, CString};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use libloading::{Library, Symbol};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use super::PluginFunctions;
+use crate::master::{EngineAction, FunctionOrigin, SliceFunction, SliceResult};
+
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+const MANIFEST_SYMBOL: &[u8] = b"myrulesiot_plugin_manifest";
+const CALL_SYMBOL: &[u8] = b"myrulesiot_plugin_call";
+const FREE_SYMBOL: &[u8] = b"myrulesiot_plugin_free";
+
+type ManifestFn = unsafe extern "C" fn() -> *const c_char;
+type CallFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub version: String,
+    // ABI version the plugin implements
+    pub abi: u32,
+    // Engine version the plugin was built against
+    pub engine: String,
+    pub functions: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum NativeError {
+    #[error("Cannot load plugin: {0}")]
+    Library(#[from] libloading::Error),
+    #[error("Invalid manifest: {0}")]
+    Manifest(String),
+    #[error("Plugin ABI version {0} not supported, expected {PLUGIN_ABI_VERSION}")]
+    Abi(u32),
+    #[error("Plugin built for engine {0} not compatible with engine {1}")]
+    Engine(String, String),
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    Some((
+        parts.next()??,
+        parts.next()??,
+        parts.next().unwrap_or(Some(0))?,
+    ))
+}
+
+// Same major version (minor for 0.x versions) and not newer than the engine
+fn engine_compatible(required: &str, engine: &str) -> bool {
+    match (parse_version(required), parse_version(engine)) {
+        (Some(required), Some(engine)) => {
+            let same_series = if required.0 == 0 {
+                engine.0 == 0 && required.1 == engine.1
+            } else {
+                required.0 == engine.0
+            };
+            same_series && required <= engine
+        }
+        _ => false,
+    }
+}
+
+pub fn check_manifest(manifest: &PluginManifest) -> Result<(), NativeError> {
+    if manifest.abi != PLUGIN_ABI_VERSION {
+        return Err(NativeError::Abi(manifest.abi));
+    }
+    let engine = env!("CARGO_PKG_VERSION");
+    if !engine_compatible(&manifest.engine, engine) {
+        return Err(NativeError::Engine(manifest.engine.clone(), engine.into()));
+    }
+    Ok(())
+}
+
+struct NativePlugin {
+    // Keeps the library loaded while its functions are registered
+    _library: Library,
+    call: CallFn,
+    free: FreeFn,
+}
+
+impl NativePlugin {
+    fn call(&self, function: &CStr, input: &str) -> Result<Value, String> {
+        let input = CString::new(input).map_err(|error| error.to_string())?;
+        // SAFETY: the plugin ABI requires a JSON C string or null, released with free
+        unsafe {
+            let result = (self.call)(function.as_ptr(), input.as_ptr());
+            if result.is_null() {
+                return Err("Function failed".into());
+            }
+            let value = serde_json::from_slice::<Value>(CStr::from_ptr(result).to_bytes());
+            (self.free)(result);
+            value.map_err(|error| error.to_string())
+        }
+    }
+}
+
+// Loads a shared library plugin and returns its manifest and rule functions
+pub fn load_native_plugin(
+    path: &Path,
+) -> Result<(PluginManifest, Vec<(String, SliceFunction)>), NativeError> {
+    // SAFETY: loading a library runs its initializers, plugins directories must be trusted
+    let (library, manifest, call, free) = unsafe {
+        let library = Library::new(path)?;
+        let manifest_fn: Symbol<ManifestFn> = library.get(MANIFEST_SYMBOL)?;
+        let manifest = manifest_fn();
+        if manifest.is_null() {
+            return Err(NativeError::Manifest("No manifest".into()));
+        }
+        let manifest =
+            serde_json::from_slice::<PluginManifest>(CStr::from_ptr(manifest).to_bytes())
+                .map_err(|error| NativeError::Manifest(error.to_string()))?;
+        let call = *library.get::<CallFn>(CALL_SYMBOL)?;
+        let free = *library.get::<FreeFn>(FREE_SYMBOL)?;
+        (library, manifest, call, free)
+    };
+    check_manifest(&manifest)?;
+
+    let plugin = Arc::new(NativePlugin {
+        _library: library,
+        call,
+        free,
+    });
+    let functions = manifest
+        .functions
+        .iter()
+        .map(|name| {
+            let plugin = plugin.clone();
+            let plugin_name = manifest.name.clone();
+            let function_name = CString::new(name.as_str())
+                .map_err(|error| NativeError::Manifest(error.to_string()))?;
+            let function: SliceFunction =
+                Box::new(move |info: &Value, action: &EngineAction| -> SliceResult {
+                    let input = json!({
+                        "info": info,
+                        "action": action.to_json(),
+                    })
+                    .to_string();
+                    match plugin
+                        .call(&function_name, &input)
+                        .and_then(SliceResult::from_json)
+                    {
+                        Ok(result) => result,
+                        Err(error) => {
+                            log::warn!(
+                                "Plugin {plugin_name} function {function_name:?} failed: {error}"
+                            );
+                            SliceResult::error(error)
+                        }
+                    }
+                });
+            Ok((name.clone(), function))
+        })
+        .collect::<Result<Vec<_>, NativeError>>()?;
+    Ok((manifest, functions))
+}
+
+// Loads all the shared libraries of a directory
+pub fn load_native_plugins(directory: &Path) -> Vec<PluginFunctions> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::info!(
+                "No native plugins loaded from {}: {error}",
+                directory.display()
+            );
+            return vec![];
+        }
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == DLL_EXTENSION))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| match load_native_plugin(&path) {
+            Ok((manifest, functions)) => {
+                log::info!(
+                    "Loaded native plugin {} {} from {} with {} functions",
+                    manifest.name,
+                    manifest.version,
+                    path.display(),
+                    functions.len()
+                );
+                Some(PluginFunctions {
+                    origin: FunctionOrigin::Native {
+                        path: path.display().to_string(),
+                        plugin: manifest.name,
+                        version: manifest.version,
+                    },
+                    functions,
+                })
+            }
+            Err(error) => {
+                log::warn!("Cannot load native plugin {}: {error}", path.display());
+                None
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,284 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+// WASM plugin ABI:
+// - export `memory` and `alloc(len: i32) -> i32` returning a buffer for the input.
+// - export `dealloc(ptr: i32, len: i32)`, called by the engine to release the input and the
+//   result once read.
+// - export every rule function as `slice_{name}(ptr: i32, len: i32) -> i64`. The input is
+//   the JSON {"info": ..., "action": ...} and the result is the pointer and length of the
+//   JSON SliceResult packed as `(ptr << 32) | len`.
+// A trap discards the instance, the next call runs on a new instance of the module.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+use wasmi::{
+    Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder,
+    TypedFunc,
+};
+
+use super::PluginFunctions;
+use crate::master::{EngineAction, FunctionOrigin, SliceFunction, SliceResult};
+
+const SLICE_PREFIX: &str = "slice_";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WasmLimits {
+    pub fuel: u64,
+    pub max_memory: usize,
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        WasmLimits {
+            fuel: 10_000_000,
+            max_memory: 16 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WasmError {
+    #[error("Cannot read plugin: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("WASM error: {0}")]
+    Wasm(#[from] wasmi::Error),
+    #[error("Plugin does not export {0}")]
+    Abi(&'static str),
+    #[error("Invalid result: {0}")]
+    Result(String),
+}
+
+struct WasmInstance {
+    store: Store<StoreLimits>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+}
+
+impl WasmInstance {
+    fn new(engine: &Engine, module: &Module, limits: WasmLimits) -> Result<Self, WasmError> {
+        let mut store = Store::new(
+            engine,
+            StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory)
+                .instances(1)
+                .build(),
+        );
+        store.limiter(|limits| limits);
+        store.set_fuel(limits.fuel)?;
+        // Plugins cannot import anything from the host
+        let instance =
+            Linker::<StoreLimits>::new(engine).instantiate_and_start(&mut store, module)?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or(WasmError::Abi("memory"))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|_| WasmError::Abi("alloc"))?;
+        let dealloc = instance
+            .get_typed_func::<(i32, i32), ()>(&store, "dealloc")
+            .map_err(|_| WasmError::Abi("dealloc"))?;
+        Ok(WasmInstance {
+            store,
+            instance,
+            memory,
+            alloc,
+            dealloc,
+        })
+    }
+
+    fn call(&mut self, export: &str, input: &[u8], fuel: u64) -> Result<Value, WasmError> {
+        self.store.set_fuel(fuel)?;
+        let func = self
+            .instance
+            .get_typed_func::<(i32, i32), i64>(&self.store, export)?;
+        let input_len = input.len() as i32;
+        let ptr = self.alloc.call(&mut self.store, input_len)?;
+        // The input is released even if it cannot be written or the call fails
+        let packed = self
+            .memory
+            .write(&mut self.store, ptr as u32 as usize, input)
+            .map_err(|error| WasmError::Result(error.to_string()))
+            .and_then(|_| Ok(func.call(&mut self.store, (ptr, input_len))? as u64));
+        let released = self.dealloc.call(&mut self.store, (ptr, input_len));
+        let packed = packed?;
+        released?;
+        let (result_ptr, result_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let data = self.memory.data(&self.store);
+        let result = data
+            .get(result_ptr..result_ptr + result_len)
+            .ok_or_else(|| WasmError::Result("Result out of memory bounds".into()))
+            .and_then(|result| {
+                serde_json::from_slice::<Value>(result)
+                    .map_err(|error| WasmError::Result(error.to_string()))
+            });
+        self.dealloc
+            .call(&mut self.store, (result_ptr as i32, result_len as i32))?;
+        result
+    }
+}
+
+struct WasmPlugin {
+    engine: Engine,
+    module: Module,
+    limits: WasmLimits,
+    instance: Option<WasmInstance>,
+}
+
+impl WasmPlugin {
+    fn new(bytes: &[u8], limits: WasmLimits) -> Result<Self, WasmError> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, bytes)?;
+        let instance = WasmInstance::new(&engine, &module, limits)?;
+        Ok(WasmPlugin {
+            engine,
+            module,
+            limits,
+            instance: Some(instance),
+        })
+    }
+
+    fn slice_names(&self) -> Vec<String> {
+        let Some(instance) = &self.instance else {
+            return vec![];
+        };
+        instance
+            .instance
+            .exports(&instance.store)
+            .map(|e| e.name().to_string())
+            .filter(|name| name.starts_with(SLICE_PREFIX))
+            .filter(|name| {
+                instance
+                    .instance
+                    .get_typed_func::<(i32, i32), i64>(&instance.store, name)
+                    .is_ok()
+            })
+            .collect()
+    }
+
+    fn call(&mut self, export: &str, input: &[u8]) -> Result<Value, WasmError> {
+        let instance = match &mut self.instance {
+            Some(instance) => instance,
+            None => {
+                self.instance
+                    .insert(WasmInstance::new(&self.engine, &self.module, self.limits)?)
+            }
+        };
+        let result = instance.call(export, input, self.limits.fuel);
+        if let Err(WasmError::Wasm(_)) = &result {
+            // The instance memory may be inconsistent after a trap
+            self.instance = None;
+        }
+        result
+    }
+}
+
+// Loads a WASM module (binary or text) and returns its rule functions
+pub fn load_wasm_plugin(
+    path: &Path,
+    limits: WasmLimits,
+) -> Result<Vec<(String, SliceFunction)>, WasmError> {
+    let plugin = WasmPlugin::new(&fs::read(path)?, limits)?;
+    let names = plugin.slice_names();
+    let plugin = Arc::new(Mutex::new(plugin));
+    let plugin_name = path.display().to_string();
+
+    Ok(names
+        .into_iter()
+        .map(|export| {
+            let plugin = plugin.clone();
+            let plugin_name = plugin_name.clone();
+            let name = export[SLICE_PREFIX.len()..].to_string();
+            let function: SliceFunction =
+                Box::new(move |info: &Value, action: &EngineAction| -> SliceResult {
+                    let input = json!({
+                        "info": info,
+                        "action": action.to_json(),
+                    })
+                    .to_string();
+                    let result = plugin.lock().unwrap().call(&export, input.as_bytes());
+                    match result
+                        .and_then(|value| SliceResult::from_json(value).map_err(WasmError::Result))
+                    {
+                        Ok(result) => result,
+                        Err(error) => {
+                            log::warn!("Plugin {plugin_name} function {export} failed: {error}");
+                            SliceResult::error(error.to_string())
+                        }
+                    }
+                });
+            (name, function)
+        })
+        .collect())
+}
+
+// Loads all the *.wasm and *.wat modules of a directory
+pub fn load_wasm_plugins(directory: &Path, limits: WasmLimits) -> Vec<PluginFunctions> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            log::info!(
+                "No WASM plugins loaded from {}: {error}",
+                directory.display()
+            );
+            return vec![];
+        }
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext == "wasm" || ext == "wat")
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| match load_wasm_plugin(&path, limits) {
+            Ok(functions) => {
+                log::info!(
+                    "Loaded WASM plugin {} with {} functions",
+                    path.display(),
+                    functions.len()
+                );
+                Some(PluginFunctions {
+                    origin: FunctionOrigin::Wasm {
+                        path: path.display().to_string(),
+                    },
+                    functions,
+                })
+            }
+            Err(error) => {
+                log::warn!("Cannot load WASM plugin {}: {error}", path.display());
+                None
+            }
+        })
+        .collect()
+}
@@ -21,13 +21,28 @@ use std::collections::HashMap;
 
 use linkme::distributed_slice;
 
-use crate::master::SliceFunction;
+use crate::master::{ParameterSpec, ParameterSpecs, SliceFunction};
 
+pub mod alarm;
+pub mod counter;
+pub mod cover;
+pub mod expression;
 pub mod forward;
+pub mod light;
+pub mod logic;
+pub mod motion;
+pub mod presence;
 pub mod relay;
+pub mod remote;
 pub mod savelist;
+pub mod sequence;
+pub mod simulation;
 pub mod startaction;
 pub mod startikea;
+pub mod statemachine;
+pub mod template;
+pub mod thermostat;
+pub mod threshold;
 pub mod timing;
 
 #[distributed_slice]
@@ -36,3 +51,11 @@ pub static SLICEFUNCTIONS: [fn() -> (String, SliceFunction)];
 pub fn distributed_engine_functions() -> HashMap<String, SliceFunction> {
     SLICEFUNCTIONS.into_iter().map(|f| f()).collect()
 }
+
+// Parameters of the functions in SLICEFUNCTIONS, registered next to each function
+#[distributed_slice]
+pub static SLICEPARAMETERS: [fn() -> (String, ParameterSpec)];
+
+pub fn distributed_parameter_specs() -> ParameterSpecs {
+    SLICEPARAMETERS.into_iter().map(|f| f()).collect()
+}
@@ -0,0 +1,207 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+pub const DISARMED: &str = "disarmed";
+pub const ARMING: &str = "arming";
+pub const ARMED_AWAY: &str = "armed_away";
+pub const ARMED_HOME: &str = "armed_home";
+pub const PENDING: &str = "pending";
+pub const TRIGGERED: &str = "triggered";
+
+// Codes are plain or "sha256:<hex digest>"
+fn valid_code(codes: &Value, code: Option<&str>) -> bool {
+    let Some(code) = code else {
+        return false;
+    };
+    codes.as_array().is_some_and(|codes| {
+        codes
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|valid| match valid.strip_prefix("sha256:") {
+                Some(digest) => hex::encode(Sha256::digest(code.as_bytes())) == digest,
+                None => valid == code,
+            })
+    })
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _alarm() -> (String, SliceFunction) {
+    (String::from("alarm"), alarm())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _alarm_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("alarm"),
+        ParameterSpec::default()
+            .optional("_keypad_topic", ParameterType::String)
+            .optional("_codes", ParameterType::Array)
+            .optional("_require_code_to_arm", ParameterType::Boolean)
+            .optional("_sensors", ParameterType::Object)
+            .optional("_exit_delay_millis", ParameterType::Integer)
+            .optional("_entry_delay_millis", ParameterType::Integer)
+            .optional("_trigger_millis", ParameterType::Integer)
+            .optional("_statetopic", ParameterType::String)
+            .optional("_sirentopic", ParameterType::String)
+            .optional("_notifytopic", ParameterType::String),
+    )
+}
+
+// Alarm panel driven by commands in _keypad_topic, {"action": "arm_away" | "arm_home" |
+// "disarm", "code": "1234", "bypass": ["sensor"]}, validated against _codes. _sensors
+// are {"name": {"topic", "pointer", "value", "modes": ["armed_away"], "entry": true}}:
+// entry sensors start the _entry_delay_millis before triggering, the others trigger
+// immediately. Arming waits _exit_delay_millis and a trigger lasts _trigger_millis.
+// States are published retained in _statetopic, the siren in _sirentopic and every
+// event is audited in _notifytopic.
+pub fn alarm() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let exit_delay = info["_exit_delay_millis"].as_i64().unwrap_or(60_000);
+        let entry_delay = info["_entry_delay_millis"].as_i64().unwrap_or(30_000);
+        let trigger_millis = info["_trigger_millis"].as_i64().unwrap_or(300_000);
+        let stateindex = &format!("alarm_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let mut alarm = info[stateindex].clone();
+        if alarm.is_null() {
+            alarm = json!({ "state": DISARMED, "since": timestamp });
+        }
+        let current = alarm["state"].as_str().unwrap_or(DISARMED).to_string();
+        let elapsed = timestamp - alarm["since"].as_i64().unwrap_or(timestamp);
+        let armed = alarm["armed"].as_str().unwrap_or(ARMED_AWAY).to_string();
+        let json_payload: Value = serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+
+        let mut audit: Vec<Value> = vec![];
+        let mut next: Option<&str> = None;
+
+        if info["_keypad_topic"]
+            .as_str()
+            .is_some_and(|keypad| action.matches(keypad))
+        {
+            let command = json_payload["action"].as_str().unwrap_or("");
+            let code_ok = valid_code(&info["_codes"], json_payload["code"].as_str());
+            let code_required = command == "disarm" || info["_require_code_to_arm"] == json!(true);
+            if code_required && !code_ok {
+                audit.push(json!({ "event": "invalid_code", "command": command }));
+            } else {
+                match command {
+                    "disarm" if current != DISARMED => next = Some(DISARMED),
+                    "arm_away" | "arm_home" if current == DISARMED => {
+                        let mode = if command == "arm_away" {
+                            ARMED_AWAY
+                        } else {
+                            ARMED_HOME
+                        };
+                        alarm["armed"] = json!(mode);
+                        alarm["bypass"] = json_payload["bypass"].clone();
+                        if !alarm["bypass"].is_null() {
+                            audit.push(json!({ "event": "bypass", "sensors": alarm["bypass"] }));
+                        }
+                        next = Some(if exit_delay > 0 { ARMING } else { mode });
+                    }
+                    _ => audit.push(json!({ "event": "ignored", "command": command })),
+                }
+            }
+        }
+
+        if let Some(sensors) = info["_sensors"].as_object() {
+            for (name, sensor) in sensors {
+                let Some(topic) = sensor["topic"].as_str() else {
+                    log::warn!("alarm: sensor {name} without topic");
+                    continue;
+                };
+                let pointer = sensor["pointer"].as_str().unwrap_or("");
+                let active = action.matches(topic)
+                    && json_payload.pointer(pointer) == Some(&sensor["value"]);
+                let bypassed = alarm["bypass"]
+                    .as_array()
+                    .is_some_and(|bypass| bypass.contains(&json!(name)));
+                let watching = match sensor["modes"].as_array() {
+                    Some(modes) => modes.contains(&json!(armed)),
+                    None => true,
+                };
+                if !active || bypassed || !watching || next.is_some() {
+                    continue;
+                }
+                let entry = sensor["entry"] == json!(true);
+                if current == ARMED_AWAY || current == ARMED_HOME {
+                    next = Some(if entry && entry_delay > 0 {
+                        PENDING
+                    } else {
+                        TRIGGERED
+                    });
+                    audit.push(json!({ "event": "sensor", "sensor": name }));
+                } else if current == PENDING && !entry {
+                    next = Some(TRIGGERED);
+                    audit.push(json!({ "event": "sensor", "sensor": name }));
+                }
+            }
+        }
+
+        if next.is_none() {
+            next = match current.as_str() {
+                ARMING if elapsed >= exit_delay => Some(armed.as_str()),
+                PENDING if elapsed >= entry_delay => Some(TRIGGERED),
+                // Back to the armed state once the siren time is over
+                TRIGGERED if elapsed >= trigger_millis => Some(armed.as_str()),
+                _ => None,
+            };
+        }
+
+        let mut messages = vec![];
+        if let Some(next) = next.filter(|next| *next != current) {
+            log::info!("Alarm changed from {current} to {next}");
+            audit.push(json!({ "event": "state", "from": current, "state": next }));
+            alarm["state"] = json!(next);
+            alarm["since"] = json!(timestamp);
+            if next == DISARMED {
+                alarm["bypass"] = json!(null);
+            }
+            if let Some(statetopic) = info["_statetopic"].as_str() {
+                let mut message = EngineMessage::new(statetopic.into(), next.into());
+                message.properties = json!({ "retain": true });
+                messages.push(message);
+            }
+            if let Some(sirentopic) = info["_sirentopic"].as_str() {
+                if next == TRIGGERED {
+                    messages.push(EngineMessage::new(sirentopic.into(), "on".into()));
+                } else if current == TRIGGERED {
+                    messages.push(EngineMessage::new(sirentopic.into(), "off".into()));
+                }
+            }
+        }
+        if let Some(notifytopic) = info["_notifytopic"].as_str() {
+            for mut event in audit {
+                event["timestamp"] = json!(timestamp);
+                messages.push(EngineMessage::new_json(notifytopic.into(), &event));
+            }
+        }
+
+        SliceResult::new(json!({ stateindex: alarm }), messages)
+    })
+}
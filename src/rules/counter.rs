@@ -0,0 +1,287 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Datelike, Local, TimeZone};
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use super::template::match_topic;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+// Watts integrated over milliseconds into kWh
+const WATT_MILLIS_TO_KWH: f64 = 1.0 / 3_600_000_000.0;
+
+pub enum Accumulation {
+    Count,
+    Sum,
+    Integral,
+}
+
+impl Accumulation {
+    fn name(&self) -> &'static str {
+        match self {
+            Accumulation::Count => "counter",
+            Accumulation::Sum => "accumulator",
+            Accumulation::Integral => "integrator",
+        }
+    }
+    fn to_json(&self, value: f64) -> Value {
+        match self {
+            Accumulation::Count if value.fract() == 0.0 => json!(value as i64),
+            _ => json!(value),
+        }
+    }
+}
+
+// The period a timestamp belongs to for the _reset schedule
+fn period(reset: &str, timestamp: i64) -> String {
+    let format = match reset {
+        "daily" => "%Y-%m-%d",
+        "monthly" => "%Y-%m",
+        _ => return String::new(),
+    };
+    Local
+        .timestamp_millis_opt(timestamp)
+        .single()
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_default()
+}
+
+// Start of the period a timestamp belongs to, in local time
+fn period_start(reset: &str, timestamp: i64) -> Option<i64> {
+    let date = Local.timestamp_millis_opt(timestamp).single()?.date_naive();
+    let date = match reset {
+        "daily" => date,
+        "monthly" => date.with_day(1)?,
+        _ => return None,
+    };
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|start| start.timestamp_millis())
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _counter() -> (String, SliceFunction) {
+    (String::from("counter"), accumulate(Accumulation::Count))
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _counter_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("counter"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_reset", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_payload", ParameterType::Any)
+            .optional("_scale", ParameterType::Number)
+            .optional("_forwardtopic", ParameterType::String)
+            .optional("_publish_millis", ParameterType::Integer),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _accumulator() -> (String, SliceFunction) {
+    (String::from("accumulator"), accumulate(Accumulation::Sum))
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _accumulator_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("accumulator"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_reset", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_scale", ParameterType::Number)
+            .optional("_cumulative", ParameterType::Boolean)
+            .optional("_forwardtopic", ParameterType::String)
+            .optional("_publish_millis", ParameterType::Integer),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _integrator() -> (String, SliceFunction) {
+    (
+        String::from("integrator"),
+        accumulate(Accumulation::Integral),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _integrator_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("integrator"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_reset", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_scale", ParameterType::Number)
+            .optional("_max_gap_millis", ParameterType::Integer)
+            .optional("_forwardtopic", ParameterType::String)
+            .optional("_publish_millis", ParameterType::Integer),
+    )
+}
+
+// Totals of the messages in _topic for the current period, restarted "daily" (default),
+// "monthly" or "never" by _reset. The counter counts messages, optionally only those
+// with _payload at _pointer. The accumulator adds the value at _pointer or, with
+// _cumulative, the increments of a meter reading, so readings received again do not
+// count twice. The integrator integrates the value at _pointer over time, by default
+// W into kWh, holding each reading at most _max_gap_millis (600000). Values are
+// multiplied by _scale. Totals are published retained in _forwardtopic every
+// _publish_millis (60000) and when the period restarts. Retained messages are ignored,
+// the broker sends them again on every connection.
+pub fn accumulate(accumulation: Accumulation) -> SliceFunction {
+    Box::new(move |info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("{}: _topic required", accumulation.name());
+            return SliceResult::empty();
+        };
+        let reset = info["_reset"].as_str().unwrap_or("daily");
+        let pointer = info["_pointer"].as_str().unwrap_or("");
+        let default_scale = match accumulation {
+            Accumulation::Integral => WATT_MILLIS_TO_KWH,
+            _ => 1.0,
+        };
+        let scale = info["_scale"].as_f64().unwrap_or(default_scale);
+        let max_gap = info["_max_gap_millis"].as_i64().unwrap_or(600_000);
+        let publish_millis = info["_publish_millis"].as_i64().unwrap_or(60_000);
+        let stateindex = &format!("{}_{}", accumulation.name(), info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let previous_state = &info[stateindex];
+        let mut value = previous_state["value"].as_f64().unwrap_or(0.0);
+        let mut previous = previous_state["previous"].clone();
+        let mut last = previous_state["last"].clone();
+
+        // The last reading held between two instants, at most _max_gap_millis after sampled
+        let held = |from: i64, until: i64| -> f64 {
+            match (last["value"].as_f64(), last["sampled_at"].as_i64()) {
+                (Some(reading), Some(sampled_at)) => {
+                    let until = until.min(sampled_at + max_gap);
+                    if until > from {
+                        reading * (until - from) as f64 * scale
+                    } else {
+                        0.0
+                    }
+                }
+                _ => 0.0,
+            }
+        };
+        let mut integrated_at = match accumulation {
+            Accumulation::Integral => last["integrated_at"].as_i64(),
+            _ => None,
+        };
+
+        // The period is kept in info so it restarts once even across engine restarts
+        let current_period = period(reset, timestamp);
+        let restarted = match previous_state["period"].as_str() {
+            Some(stored) => stored != current_period,
+            None => false,
+        };
+        if restarted {
+            // The integral until the period start belongs to the previous period
+            if let (Some(from), Some(start)) = (integrated_at, period_start(reset, timestamp)) {
+                value += held(from, start);
+                integrated_at = Some(from.max(start));
+            }
+            previous = accumulation.to_json(value);
+            value = 0.0;
+        }
+
+        // Integrates the last reading until now, the total stays current on every tick
+        if let Some(from) = integrated_at {
+            value += held(from, timestamp);
+            last["integrated_at"] = json!(timestamp.max(from));
+        }
+
+        if !action.retain && match_topic(topic, &action.topic).is_some() {
+            let json_payload: Value = serde_json::from_slice(&action.payload)
+                .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload)));
+            let reading = json_payload.pointer(pointer);
+            match accumulation {
+                Accumulation::Count => {
+                    if info["_payload"].is_null() || reading == Some(&info["_payload"]) {
+                        value += scale;
+                    }
+                }
+                Accumulation::Sum => {
+                    if let Some(reading) = reading.and_then(Value::as_f64) {
+                        if info["_cumulative"] == json!(true) {
+                            // A reading lower than the last one is a meter reset
+                            if let Some(last_reading) = last["value"].as_f64() {
+                                let increment = if reading >= last_reading {
+                                    reading - last_reading
+                                } else {
+                                    reading
+                                };
+                                value += increment * scale;
+                            }
+                            last = json!({ "value": reading });
+                        } else {
+                            value += reading * scale;
+                        }
+                    }
+                }
+                Accumulation::Integral => {
+                    if let Some(reading) = reading.and_then(Value::as_f64) {
+                        last = json!({
+                            "value": reading,
+                            "sampled_at": timestamp,
+                            "integrated_at": timestamp,
+                        });
+                    }
+                }
+            }
+        }
+
+        let published_at = previous_state["published_at"].as_i64();
+        let publish = restarted || published_at.is_none_or(|at| timestamp - at >= publish_millis);
+        let mut messages = vec![];
+        let published_at = match info["_forwardtopic"].as_str() {
+            Some(topic) if publish => {
+                let mut message = EngineMessage::new_json(
+                    topic.into(),
+                    &json!({
+                        "value": accumulation.to_json(value),
+                        "previous": previous,
+                        "period": current_period,
+                    }),
+                );
+                message.properties = json!({ "retain": true });
+                messages.push(message);
+                Some(timestamp)
+            }
+            _ => published_at,
+        };
+
+        SliceResult::new(
+            json!({
+                stateindex: {
+                    "value": accumulation.to_json(value),
+                    "previous": previous,
+                    "period": current_period,
+                    "last": last,
+                    "published_at": published_at,
+                }
+            }),
+            messages,
+        )
+    })
+}
@@ -0,0 +1,234 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+// Current position estimated from the travel time, 0 closed and 100 open
+fn estimate_position(state: &Value, travel_millis: i64, timestamp: i64) -> f64 {
+    let position = state["position"].as_f64().unwrap_or(0.0);
+    let Some(started_at) = state["started_at"].as_i64() else {
+        return position;
+    };
+    let travelled = (timestamp - started_at) as f64 * 100.0 / travel_millis as f64;
+    match state["moving"].as_str() {
+        Some("up") => (position + travelled).min(100.0),
+        Some("down") => (position - travelled).max(0.0),
+        _ => position,
+    }
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _cover() -> (String, SliceFunction) {
+    (String::from("cover"), cover())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _cover_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("cover"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_forwardtopic", ParameterType::String)
+            .optional("_travel_millis", ParameterType::Integer)
+            .optional("_up", ParameterType::String)
+            .optional("_down", ParameterType::String)
+            .optional("_stop", ParameterType::String)
+            .optional("_statetopic", ParameterType::String)
+            .optional("_reverse_delay_millis", ParameterType::Integer),
+    )
+}
+
+// Controls a cover motor in _forwardtopic, with payloads _up, _down and _stop, from the
+// commands in _topic: "open", "close", "stop", a position or {"position": 40}. The
+// position is estimated from _travel_millis, the time to fully open or close, and the
+// timer stops the motor at the target. Open and close always run the full travel when
+// the position is unknown. Reversing stops the motor first and starts the other
+// direction _reverse_delay_millis later, 500 by default, keeping the position while
+// stopped. Changes are published retained in _statetopic.
+pub fn cover() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("cover: _topic required");
+            return SliceResult::empty();
+        };
+        let Some(forwardtopic) = info["_forwardtopic"].as_str() else {
+            log::warn!("cover: _forwardtopic required");
+            return SliceResult::empty();
+        };
+        let travel_millis = info["_travel_millis"].as_i64().unwrap_or(30_000);
+        let reverse_delay_millis = info["_reverse_delay_millis"].as_i64().unwrap_or(500);
+        let stateindex = &format!("cover_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let state = &info[stateindex];
+        let moving = state["moving"].as_str();
+        let reversing = state["reversing"].as_str();
+        let position = estimate_position(state, travel_millis, timestamp);
+        let motor = |command: &str| -> EngineMessage {
+            let payload = info[format!("_{command}")].as_str().unwrap_or(command);
+            EngineMessage::new(forwardtopic.into(), payload.into())
+        };
+
+        let (newstate, mut messages) = if action.matches(topic) {
+            let payload = String::from_utf8_lossy(&action.payload);
+            let json_payload: Value = serde_json::from_str(&payload).unwrap_or(json!(null));
+            let known = !state["position"].is_null();
+            let (start, target) = match payload.trim() {
+                "open" => (if known { position } else { 0.0 }, 100.0),
+                "close" => (if known { position } else { 100.0 }, 0.0),
+                "stop" => (position, position),
+                _ => match json_payload["position"].as_f64().or(json_payload.as_f64()) {
+                    Some(target) => (position, target.clamp(0.0, 100.0)),
+                    None => {
+                        log::warn!("cover: unknown command {payload}");
+                        return SliceResult::empty();
+                    }
+                },
+            };
+            let direction = if target > start {
+                Some("up")
+            } else if target < start {
+                Some("down")
+            } else {
+                None
+            };
+            match direction {
+                // The motor stops before reversing, the position is kept until it restarts
+                Some(direction) if moving.is_some_and(|moving| moving != direction) => {
+                    if reverse_delay_millis > 0 {
+                        (
+                            json!({
+                                "position": start,
+                                "reversing": direction,
+                                "resume_at": timestamp + reverse_delay_millis,
+                                "target": target,
+                            }),
+                            vec![motor("stop")],
+                        )
+                    } else {
+                        (
+                            json!({
+                                "position": start,
+                                "moving": direction,
+                                "started_at": timestamp,
+                                "target": target,
+                            }),
+                            vec![motor("stop"), motor(direction)],
+                        )
+                    }
+                }
+                Some(direction) if reversing == Some(direction) => (
+                    json!({
+                        "position": start,
+                        "reversing": direction,
+                        "resume_at": state["resume_at"],
+                        "target": target,
+                    }),
+                    vec![],
+                ),
+                Some(direction) => (
+                    json!({
+                        "position": start,
+                        "moving": direction,
+                        "started_at": timestamp,
+                        "target": target,
+                    }),
+                    if moving == Some(direction) {
+                        vec![]
+                    } else {
+                        vec![motor(direction)]
+                    },
+                ),
+                None => (
+                    json!({ "position": start }),
+                    if moving.is_some() {
+                        vec![motor("stop")]
+                    } else {
+                        vec![]
+                    },
+                ),
+            }
+        } else if let Some(direction) = moving {
+            let target = state["target"].as_f64().unwrap_or(position);
+            let reached = match direction {
+                "up" => position >= target,
+                _ => position <= target,
+            };
+            if !reached {
+                return SliceResult::empty();
+            }
+            (json!({ "position": target }), vec![motor("stop")])
+        } else if let Some(direction) = reversing {
+            if state["resume_at"]
+                .as_i64()
+                .is_some_and(|resume_at| timestamp < resume_at)
+            {
+                return SliceResult::empty();
+            }
+            (
+                json!({
+                    "position": position,
+                    "moving": direction,
+                    "started_at": timestamp,
+                    "target": state["target"],
+                }),
+                vec![motor(direction)],
+            )
+        } else {
+            return SliceResult::empty();
+        };
+
+        if let Some(statetopic) = info["_statetopic"].as_str() {
+            let mut message = EngineMessage::new_json(
+                statetopic.into(),
+                &json!({
+                    "position": newstate["position"].as_f64().unwrap_or(0.0).round(),
+                    "state": match newstate["moving"].as_str().or(newstate["reversing"].as_str()) {
+                        Some("up") => "opening",
+                        Some(_) => "closing",
+                        None => "stopped",
+                    },
+                }),
+            );
+            message.properties = json!({ "retain": true });
+            messages.push(message);
+        }
+        // Replaces the previous state so no stale movement is kept
+        SliceResult::new(
+            json!({ stateindex: replace_patch(state, newstate) }),
+            messages,
+        )
+    })
+}
+
+// Merge patch that turns the previous state into the new one
+fn replace_patch(previous: &Value, mut new: Value) -> Value {
+    if let (Some(previous), Some(new)) = (previous.as_object(), new.as_object_mut()) {
+        for key in previous.keys() {
+            new.entry(key.clone()).or_insert(Value::Null);
+        }
+    }
+    new
+}
@@ -0,0 +1,104 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::expression::{self, truthy};
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _start_expression() -> (String, SliceFunction) {
+    (String::from("start_expression"), start_expression())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _start_expression_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_expression"),
+        ParameterSpec::default()
+            .required("_expression", ParameterType::String)
+            .optional("_topic", ParameterType::String),
+    )
+}
+
+pub fn start_expression() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(source) = info["_expression"].as_str() else {
+            log::warn!("start_expression: _expression required");
+            return SliceResult::state(json!({ "_start" : false }));
+        };
+        // Without topic the expression is evaluated for every action
+        if let Some(topic) = info["_topic"].as_str() {
+            if !action.matches(topic) {
+                return SliceResult::state(json!({ "_start" : false }));
+            }
+        }
+        let start = match expression::evaluate(source, info, action) {
+            Ok(value) => truthy(&value),
+            Err(error) => {
+                log::warn!("start_expression: {source}: {error}");
+                false
+            }
+        };
+        SliceResult::state(json!({ "_start" : start }))
+    })
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _publish_expression() -> (String, SliceFunction) {
+    (String::from("publish_expression"), publish_expression())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _publish_expression_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("publish_expression"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_expression", ParameterType::String),
+    )
+}
+
+pub fn publish_expression() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        if info["_start"] != json!(true) {
+            return SliceResult::empty();
+        }
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("publish_expression: _topic required");
+            return SliceResult::empty();
+        };
+        let Some(source) = info["_expression"].as_str() else {
+            log::warn!("publish_expression: _expression required");
+            return SliceResult::empty();
+        };
+        match expression::evaluate(source, info, action) {
+            Ok(Value::String(s)) => {
+                SliceResult::messages(vec![EngineMessage::new(topic.into(), s.into_bytes())])
+            }
+            Ok(value) => SliceResult::messages(vec![EngineMessage::new_json(topic.into(), &value)]),
+            Err(error) => {
+                log::warn!("publish_expression: {source}: {error}");
+                SliceResult::empty()
+            }
+        }
+    })
+}
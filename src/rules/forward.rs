@@ -24,13 +24,23 @@ use serde_json::Value;
 use crate::master::SliceFunction;
 use crate::master::SliceResult;
 use crate::master::{EngineAction, EngineMessage};
+use crate::master::{ParameterSpec, ParameterType};
 
-use super::SLICEFUNCTIONS;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
 
 #[distributed_slice(SLICEFUNCTIONS)]
 fn _forward_user_action() -> (String, SliceFunction) {
     (String::from("forward_user_action"), forward_user_action())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn _forward_user_action_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("forward_user_action"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_forwardtopic", ParameterType::String),
+    )
+}
 
 pub fn forward_user_action() -> SliceFunction {
     Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
@@ -50,6 +60,15 @@ pub fn forward_user_action() -> SliceFunction {
 fn _forward_action() -> (String, SliceFunction) {
     (String::from("forward_action"), forward_action())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn _forward_action_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("forward_action"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_forwardtopic", ParameterType::String),
+    )
+}
 
 pub fn forward_action() -> SliceFunction {
     Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
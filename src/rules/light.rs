@@ -0,0 +1,240 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Map, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+// The light attributes tracked from its zigbee2mqtt state topic
+const TRACKED: [&str; 4] = ["state", "brightness", "color_temp", "color"];
+const MAX_BRIGHTNESS: i64 = 254;
+
+pub enum LightCommand {
+    StepUp,
+    StepDown,
+    SetLevel,
+    ColorTemp,
+    Scene,
+    SceneStore,
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_step_up() -> (String, SliceFunction) {
+    (String::from("light_step_up"), light(LightCommand::StepUp))
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_step_up_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_step_up"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_step", ParameterType::Integer)
+            .optional("_transition", ParameterType::Number),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_step_down() -> (String, SliceFunction) {
+    (
+        String::from("light_step_down"),
+        light(LightCommand::StepDown),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_step_down_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_step_down"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_step", ParameterType::Integer)
+            .optional("_transition", ParameterType::Number),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_set_level() -> (String, SliceFunction) {
+    (
+        String::from("light_set_level"),
+        light(LightCommand::SetLevel),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_set_level_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_set_level"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_brightness", ParameterType::Integer)
+            .optional("_percent", ParameterType::Integer)
+            .optional("_transition", ParameterType::Number),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_color_temp() -> (String, SliceFunction) {
+    (
+        String::from("light_color_temp"),
+        light(LightCommand::ColorTemp),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_color_temp_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_color_temp"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_color_temp", ParameterType::Integer)
+            .optional("_transition", ParameterType::Number),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_scene() -> (String, SliceFunction) {
+    (String::from("light_scene"), light(LightCommand::Scene))
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_scene_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_scene"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_scene", ParameterType::Any)
+            .optional("_transition", ParameterType::Number),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn light_scene_store() -> (String, SliceFunction) {
+    (
+        String::from("light_scene_store"),
+        light(LightCommand::SceneStore),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn light_scene_store_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("light_scene_store"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_scene", ParameterType::Any),
+    )
+}
+
+// Tracks in info["light_state"] the state published by the light in _topic and when
+// _start sends the command to _topic/set. Brightness goes from 0 to 254, _step by
+// default 32, and _transition is in seconds.
+pub fn light(command: LightCommand) -> SliceFunction {
+    Box::new(move |info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("light: _topic required");
+            return SliceResult::empty();
+        };
+        let mut light = info["light_state"][topic]
+            .as_object()
+            .cloned()
+            .unwrap_or_else(Map::new);
+
+        if action.matches(topic) {
+            let json_payload: Value =
+                serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+            for attribute in TRACKED {
+                if let Some(value) = json_payload.get(attribute) {
+                    light.insert(attribute.into(), value.clone());
+                }
+            }
+            return SliceResult::state(json!({ "light_state": { topic: light } }));
+        }
+        if info["_start"] != json!(true) {
+            return SliceResult::empty();
+        }
+
+        let brightness = if light.get("state") == Some(&json!("OFF")) {
+            0
+        } else {
+            light.get("brightness").and_then(Value::as_i64).unwrap_or(0)
+        };
+        let step = info["_step"].as_i64().unwrap_or(32);
+        let mut set = match command {
+            LightCommand::StepUp => {
+                json!({ "state": "ON", "brightness": (brightness + step).min(MAX_BRIGHTNESS) })
+            }
+            LightCommand::StepDown => {
+                let level = brightness - step;
+                if level > 0 {
+                    json!({ "brightness": level })
+                } else {
+                    json!({ "state": "OFF" })
+                }
+            }
+            LightCommand::SetLevel => {
+                let level = info["_brightness"].as_i64().or_else(|| {
+                    info["_percent"]
+                        .as_i64()
+                        .map(|percent| percent * MAX_BRIGHTNESS / 100)
+                });
+                let Some(level) = level else {
+                    log::warn!("light: _brightness or _percent required for {topic}");
+                    return SliceResult::empty();
+                };
+                let level = level.clamp(0, MAX_BRIGHTNESS);
+                if level > 0 {
+                    json!({ "state": "ON", "brightness": level })
+                } else {
+                    json!({ "state": "OFF" })
+                }
+            }
+            LightCommand::ColorTemp => match required(info, "_color_temp", topic) {
+                Some(color_temp) => json!({ "color_temp": color_temp }),
+                None => return SliceResult::empty(),
+            },
+            LightCommand::Scene => match required(info, "_scene", topic) {
+                Some(scene) => json!({ "scene_recall": scene }),
+                None => return SliceResult::empty(),
+            },
+            LightCommand::SceneStore => match required(info, "_scene", topic) {
+                Some(scene) => json!({ "scene_store": scene }),
+                None => return SliceResult::empty(),
+            },
+        };
+        if let Some(transition) = info["_transition"].as_f64() {
+            set["transition"] = json!(transition);
+        }
+
+        // Updated optimistically so repeated steps do not wait for the light state
+        for attribute in TRACKED {
+            if let Some(value) = set.get(attribute) {
+                light.insert(attribute.into(), value.clone());
+            }
+        }
+        SliceResult::new(
+            json!({ "light_state": { topic: light } }),
+            vec![EngineMessage::new_json(format!("{topic}/set"), &set)],
+        )
+    })
+}
+
+// The command is skipped when the parameter it sends is missing
+fn required<'a>(info: &'a Value, parameter: &str, topic: &str) -> Option<&'a Value> {
+    let value = &info[parameter];
+    if value.is_null() {
+        log::warn!("light: {parameter} required for {topic}");
+        return None;
+    }
+    Some(value)
+}
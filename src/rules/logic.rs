@@ -0,0 +1,123 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Map, Value};
+
+use super::template::match_topic;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::expression::{self, truthy, Context};
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _condition_logic() -> (String, SliceFunction) {
+    (String::from("condition_logic"), condition_logic())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _condition_logic_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("condition_logic"),
+        ParameterSpec::default()
+            .required("_inputs", ParameterType::Object)
+            .required("_expression", ParameterType::String),
+    )
+}
+
+// Keeps the latest value of every input in _inputs, {"name": "topic"} or
+// {"name": {"topic": "topic", "pointer": "/value"}}, and evaluates the boolean
+// _expression over them, as inputs.name. Starts when the expression becomes true.
+pub fn condition_logic() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(inputs) = info["_inputs"].as_object() else {
+            log::warn!("condition_logic: _inputs required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let Some(source) = info["_expression"].as_str() else {
+            log::warn!("condition_logic: _expression required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let stateindex = &format!("condition_logic_{}", info["_index"]);
+
+        // Values are kept as JSON text, merging objects would drop their null fields
+        // and keep the fields removed
+        let mut values: Map<String, Value> = info[stateindex]["inputs"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, value)| {
+                let value = serde_json::from_str::<Value>(value.as_str()?).ok()?;
+                Some((name.clone(), value))
+            })
+            .collect();
+        let mut updated = false;
+        for (name, input) in inputs {
+            let (topic, pointer) = match input {
+                Value::String(topic) => (topic.as_str(), ""),
+                input => {
+                    let Some(topic) = input["topic"].as_str() else {
+                        log::warn!("condition_logic: input {name} without topic");
+                        continue;
+                    };
+                    (topic, input["pointer"].as_str().unwrap_or(""))
+                }
+            };
+            if match_topic(topic, &action.topic).is_none() {
+                continue;
+            }
+            let json_payload: Value = serde_json::from_slice(&action.payload)
+                .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload)));
+            if let Some(value) = json_payload.pointer(pointer) {
+                values.insert(name.clone(), value.clone());
+                updated = true;
+            }
+        }
+        if !updated {
+            return SliceResult::state(json!({ "_start": false }));
+        }
+
+        let values = Value::Object(values);
+        let was_active = info[stateindex]["active"] == json!(true);
+        let active = match expression::parse(source).and_then(|expr| {
+            expression::eval(
+                &expr,
+                &Context::new(info, action).with_inputs(values.clone()),
+            )
+        }) {
+            Ok(value) => truthy(&value),
+            Err(error) => {
+                log::warn!("condition_logic: {source}: {error}");
+                false
+            }
+        };
+
+        let stored: Map<String, Value> = values
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(name, value)| (name.clone(), json!(value.to_string())))
+            .collect();
+        SliceResult::state(json!({
+            stateindex: {
+                "inputs": stored,
+                "active": active,
+            },
+            "_start": active && !was_active
+        }))
+    })
+}
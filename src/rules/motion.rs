@@ -0,0 +1,136 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _motion_light() -> (String, SliceFunction) {
+    (String::from("motion_light"), motion_light())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _motion_light_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("motion_light"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_forwardtopic", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_millis", ParameterType::Integer)
+            .optional("_on", ParameterType::String)
+            .optional("_off", ParameterType::String)
+            .optional("_manual_topic", ParameterType::String)
+            .optional("_override_millis", ParameterType::Integer)
+            .optional("_max_illuminance", ParameterType::Number)
+            .optional("_illuminance_pointer", ParameterType::String),
+    )
+}
+
+// Switches on _forwardtopic when the sensor in _topic reports occupancy and switches it
+// off _millis after the last occupancy. Occupancy is not acted on while illuminance is
+// at or above _max_illuminance, or during _override_millis after a message in _manual_topic.
+pub fn motion_light() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("motion_light: _topic required");
+            return SliceResult::empty();
+        };
+        let Some(forwardtopic) = info["_forwardtopic"].as_str() else {
+            log::warn!("motion_light: _forwardtopic required");
+            return SliceResult::empty();
+        };
+        let pointer = info["_pointer"].as_str().unwrap_or("/occupancy");
+        let millis = info["_millis"].as_i64().unwrap_or(120_000);
+        let on = info["_on"].as_str().unwrap_or("on");
+        let off = info["_off"].as_str().unwrap_or("off");
+        let stateindex = &format!("motion_light_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let state = &info[stateindex];
+        let light_on = state["on"] == json!(true);
+        let last_motion = state["last_motion"].as_i64();
+        let overridden = state["override_until"]
+            .as_i64()
+            .is_some_and(|until| timestamp < until);
+
+        if info["_manual_topic"]
+            .as_str()
+            .is_some_and(|manual| action.matches(manual))
+        {
+            let override_millis = info["_override_millis"].as_i64().unwrap_or(3_600_000);
+            return SliceResult::state(json!({
+                stateindex: {
+                    "on": action.payload == on.as_bytes(),
+                    "last_motion": timestamp,
+                    "override_until": timestamp + override_millis,
+                }
+            }));
+        }
+
+        if action.matches(topic) {
+            let json_payload: Value =
+                serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+            if overridden || json_payload.pointer(pointer) != Some(&json!(true)) {
+                return SliceResult::empty();
+            }
+            let too_bright = info["_max_illuminance"].as_f64().is_some_and(|max| {
+                let illuminance_pointer = info["_illuminance_pointer"]
+                    .as_str()
+                    .unwrap_or("/illuminance_lux");
+                json_payload
+                    .pointer(illuminance_pointer)
+                    .and_then(Value::as_f64)
+                    .is_some_and(|illuminance| illuminance >= max)
+            });
+            if !light_on && too_bright {
+                return SliceResult::empty();
+            }
+            // Retriggering while on extends the timeout
+            return SliceResult::new(
+                json!({
+                    stateindex: {
+                        "on": true,
+                        "last_motion": timestamp,
+                        "override_until": null,
+                    }
+                }),
+                if light_on {
+                    vec![]
+                } else {
+                    vec![EngineMessage::new(forwardtopic.into(), on.into())]
+                },
+            );
+        }
+
+        if light_on && !overridden && last_motion.is_some_and(|last| timestamp - last >= millis) {
+            return SliceResult::new(
+                json!({ stateindex: null }),
+                vec![EngineMessage::new(forwardtopic.into(), off.into())],
+            );
+        }
+
+        SliceResult::empty()
+    })
+}
@@ -0,0 +1,175 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Local, TimeZone, Timelike};
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::timing::parse_time;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+// The household mode is kept in info["presence"]["mode"], functions with the
+// _modes parameter only run in the modes listed.
+pub const MODES: [&str; 4] = ["home", "away", "night", "vacation"];
+
+fn is_night(night: &Value, timestamp: i64) -> bool {
+    let (Some(from), Some(to), Some(now)) = (
+        night["from"].as_str().and_then(parse_time),
+        night["to"].as_str().and_then(parse_time),
+        Local.timestamp_millis_opt(timestamp).single(),
+    ) else {
+        return false;
+    };
+    let minute = now.hour() as i64 * 60 + now.minute() as i64;
+    if from <= to {
+        minute >= from && minute < to
+    } else {
+        minute >= from || minute < to
+    }
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _presence() -> (String, SliceFunction) {
+    (String::from("presence"), presence())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _presence_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("presence"),
+        ParameterSpec::default()
+            .optional("_trackers", ParameterType::Object)
+            .optional("_doors", ParameterType::Array)
+            .optional("_away_delay_millis", ParameterType::Integer)
+            .optional("_vacation_after_millis", ParameterType::Integer)
+            .optional("_night", ParameterType::Object)
+            .optional("_manual_topic", ParameterType::String)
+            .optional("_forwardtopic", ParameterType::String),
+    )
+}
+
+// Aggregates the _trackers, {"name": "topic"} or {"name": {"topic": "topic", "pointer":
+// "/state", "home": "home"}}, into the household mode. The house is away _away_delay_millis
+// after everybody left and no message from the _doors topics arrived, vacation after
+// _vacation_after_millis away, and night inside the _night {"from", "to"} period while
+// home. Messages in _manual_topic force a mode until "auto". Mode changes are published
+// retained in _forwardtopic.
+pub fn presence() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let away_delay_millis = info["_away_delay_millis"].as_i64().unwrap_or(600_000);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+        let mut presence = info["presence"].clone();
+        if presence.is_null() {
+            presence = json!({ "mode": "home", "since": timestamp });
+        }
+        let json_payload: Value = serde_json::from_slice(&action.payload)
+            .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload)));
+
+        let anybody_home = |presence: &Value| {
+            presence["trackers"]
+                .as_object()
+                .is_some_and(|trackers| trackers.values().any(|home| home == &json!(true)))
+        };
+        let was_home = anybody_home(&presence);
+        if let Some(trackers) = info["_trackers"].as_object() {
+            for (name, tracker) in trackers {
+                let (topic, pointer, home) = match tracker {
+                    Value::String(topic) => (topic.as_str(), "", json!("home")),
+                    tracker => {
+                        let Some(topic) = tracker["topic"].as_str() else {
+                            log::warn!("presence: tracker {name} without topic");
+                            continue;
+                        };
+                        (
+                            topic,
+                            tracker["pointer"].as_str().unwrap_or(""),
+                            tracker.get("home").cloned().unwrap_or(json!("home")),
+                        )
+                    }
+                };
+                if action.matches(topic) {
+                    if let Some(value) = json_payload.pointer(pointer) {
+                        presence["trackers"][name] = json!(value == &home);
+                    }
+                }
+            }
+        }
+        let is_home = anybody_home(&presence);
+        if !is_home && (was_home || presence["left_at"].is_null()) {
+            presence["left_at"] = json!(timestamp);
+        }
+        if let Some(doors) = info["_doors"].as_array() {
+            if doors
+                .iter()
+                .any(|door| door.as_str() == Some(&action.topic))
+            {
+                presence["last_door"] = json!(timestamp);
+            }
+        }
+        if info["_manual_topic"]
+            .as_str()
+            .is_some_and(|manual| action.matches(manual))
+        {
+            presence["manual"] = match json_payload.as_str() {
+                Some(mode) if MODES.contains(&mode) => json!(mode),
+                _ => json!(null),
+            };
+        }
+
+        let current = presence["mode"].as_str().unwrap_or("home").to_string();
+        let mode = if let Some(manual) = presence["manual"].as_str() {
+            manual.to_string()
+        } else if is_home || presence["trackers"].is_null() {
+            if is_night(&info["_night"], timestamp) {
+                "night".into()
+            } else {
+                "home".into()
+            }
+        } else {
+            let left_at = presence["left_at"].as_i64().unwrap_or(timestamp);
+            let quiet_since = left_at.max(presence["last_door"].as_i64().unwrap_or(left_at));
+            let vacation = info["_vacation_after_millis"]
+                .as_i64()
+                .is_some_and(|millis| timestamp - left_at >= millis);
+            if vacation || current == "vacation" {
+                "vacation".into()
+            } else if timestamp - quiet_since >= away_delay_millis {
+                "away".into()
+            } else {
+                current.clone()
+            }
+        };
+
+        let mut messages = vec![];
+        if mode != current {
+            log::info!("Presence mode changed from {current} to {mode}");
+            presence["since"] = json!(timestamp);
+            if let Some(forwardtopic) = info["_forwardtopic"].as_str() {
+                let mut message = EngineMessage::new(forwardtopic.into(), mode.clone().into());
+                message.properties = json!({ "retain": true });
+                messages.push(message);
+            }
+        }
+        presence["mode"] = json!(mode);
+        SliceResult::new(json!({ "presence": presence }), messages)
+    })
+}
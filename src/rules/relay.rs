@@ -20,14 +20,25 @@
 use linkme::distributed_slice;
 use serde_json::{json, Value};
 
-use crate::master::{EngineAction, EngineMessage, SliceFunction, SliceResult};
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
 
-use super::SLICEFUNCTIONS;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
 
 #[distributed_slice(SLICEFUNCTIONS)]
 fn relay_action() -> (String, SliceFunction) {
     (String::from("relay"), relay())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn relay_action_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("relay"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_value", ParameterType::String),
+    )
+}
 
 pub fn relay() -> SliceFunction {
     Box::new(|info: &Value, _action: &EngineAction| {
@@ -41,10 +52,24 @@ pub fn relay() -> SliceFunction {
 fn relay_value_on() -> (String, SliceFunction) {
     (String::from("relay_on"), relay_value(b"on"))
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn relay_value_on_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("relay_on"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn relay_value_off() -> (String, SliceFunction) {
     (String::from("relay_off"), relay_value(b"off"))
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn relay_value_off_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("relay_off"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 
 pub fn relay_value(value: &[u8]) -> SliceFunction {
     let value: Vec<u8> = value.into();
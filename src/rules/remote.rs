@@ -0,0 +1,165 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::sync::OnceLock;
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+// Vendor profiles map generic action names to the actions each remote publishes
+pub fn remote_profiles() -> &'static Value {
+    static PROFILES: OnceLock<Value> = OnceLock::new();
+    PROFILES.get_or_init(|| serde_json::from_str(include_str!("remote/profiles.json")).unwrap())
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _start_remote() -> (String, SliceFunction) {
+    (String::from("start_remote"), start_remote())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _start_remote_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_remote"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_action", ParameterType::String)
+            .required("_profile", ParameterType::Any)
+            .optional("_double_millis", ParameterType::Integer)
+            .optional("_wait_double", ParameterType::Boolean),
+    )
+}
+
+// Starts when the remote in _topic publishes the generic _action of the vendor _profile,
+// a profile name or an inline profile object. Actions ending in _double not defined by
+// the profile are detected as two presses within _double_millis. With _wait_double the action
+// only starts once _double_millis pass without a second press, so a double click does not
+// also start it. Releases expose the time the button was held as _hold_millis.
+pub fn start_remote() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("start_remote: _topic required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let Some(generic) = info["_action"].as_str() else {
+            log::warn!("start_remote: _action required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let profile = match &info["_profile"] {
+            Value::String(name) => &remote_profiles()[name],
+            profile => profile,
+        };
+        if !profile.is_object() {
+            log::warn!("start_remote: _profile required, a known profile name or a profile");
+            return SliceResult::state(json!({ "_start": false }));
+        }
+        let stateindex = &format!("start_remote_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+        let double_millis = info["_double_millis"].as_i64().unwrap_or(500);
+
+        // A press held back waiting for a double click starts once the window expires
+        let pending = info[stateindex]["pending"].as_i64();
+        let expired = pending.is_some_and(|pending| timestamp - pending > double_millis);
+        let result = |mut state: Value| {
+            if expired {
+                state[stateindex] = json!(null);
+            }
+            SliceResult::state(state)
+        };
+
+        if !action.matches(topic) {
+            return result(json!({ "_start": expired }));
+        }
+        let pointer = profile["pointer"].as_str().unwrap_or("/action");
+        let json_payload: Value = serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+        let Some(received) = json_payload.pointer(pointer).and_then(Value::as_str) else {
+            return result(json!({ "_start": expired }));
+        };
+        let actions = &profile["actions"];
+
+        // Double click detected by timing
+        if let (None, Some(single)) = (actions[generic].as_str(), generic.strip_suffix("_double")) {
+            if actions[single].as_str() != Some(received) {
+                return SliceResult::state(json!({ "_start": false }));
+            }
+            let double = info[stateindex]["click"]
+                .as_i64()
+                .is_some_and(|click| timestamp - click <= double_millis);
+            return SliceResult::state(json!({
+                stateindex: { "click": if double { json!(null) } else { json!(timestamp) } },
+                "_start": double
+            }));
+        }
+
+        let Some(expected) = actions[generic].as_str() else {
+            log::warn!("start_remote: action {generic} not defined in profile");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let pressed = received == expected;
+        if pressed && info["_wait_double"] == json!(true) {
+            // The second press within the window is the double click
+            let pending = if expired || pending.is_none() {
+                json!(timestamp)
+            } else {
+                json!(null)
+            };
+            return SliceResult::state(json!({
+                stateindex: { "pending": pending },
+                "_start": expired
+            }));
+        }
+        let start = pressed || expired;
+
+        // Holds are tracked per remote so any rule on the release knows the hold time.
+        // Remotes with a single button publish bare hold and release actions.
+        let received_generic = actions
+            .as_object()
+            .and_then(|actions| actions.iter().find(|(_, a)| a.as_str() == Some(received)))
+            .map(|(name, _)| name.as_str())
+            .unwrap_or("");
+        if let Some(button) = remote_button(received_generic, "hold") {
+            return result(json!({
+                "start_remote_hold": { topic: { button: timestamp } },
+                "_start": start
+            }));
+        }
+        if let Some(button) = remote_button(received_generic, "release") {
+            let hold_millis = info["start_remote_hold"][topic][button]
+                .as_i64()
+                .map(|hold| timestamp - hold);
+            return result(json!({
+                "_hold_millis": hold_millis,
+                "_start": start
+            }));
+        }
+        result(json!({ "_start": start }))
+    })
+}
+
+// Button of a generic action like left_hold, the bare action is the remote single button
+fn remote_button<'a>(generic: &'a str, suffix: &str) -> Option<&'a str> {
+    if generic == suffix {
+        Some("")
+    } else {
+        generic.strip_suffix(suffix)?.strip_suffix('_')
+    }
+}
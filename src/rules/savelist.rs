@@ -31,7 +31,7 @@ pub fn save_list(info: &Value, action: &EngineAction) -> SliceResult {
 
     let topic_store = format!("{}/list", topic);
 
-    if action.matches(&topic) {
+    if action.matches(topic) {
         match serde_json::from_slice::<Value>(&action.payload) {
             Ok(value) => {
                 return SliceResult::state(json!({
@@ -0,0 +1,166 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use super::template::{match_topic, TemplateContext};
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::expression::{self, truthy};
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _sequence() -> (String, SliceFunction) {
+    (String::from("sequence"), sequence())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _sequence_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("sequence"),
+        ParameterSpec::default()
+            .required("_steps", ParameterType::Array)
+            .optional("_topic", ParameterType::String)
+            .optional("_cancel_topic", ParameterType::String)
+            .optional("_restart", ParameterType::Boolean),
+    )
+}
+
+// Runs the _steps in order when an action matches _topic or, without topic, on _start.
+// Steps are {"publish": "topic", "payload", "json", "qos", "retain"} templates rendered
+// with the triggering action, {"wait_millis": 30000} or {"condition": "expression"}
+// that ends the sequence when false. A trigger while running restarts the sequence
+// unless _restart is false and an action matching _cancel_topic cancels it. The
+// running step is kept in info, steps due while the engine was stopped run on the
+// first action after the restart. Starts when the sequence completes.
+pub fn sequence() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(steps) = info["_steps"].as_array() else {
+            log::warn!("sequence: _steps required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let stateindex = &format!("sequence_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let mut sequence = info[stateindex].clone();
+        let running = !sequence.is_null();
+
+        if running
+            && info["_cancel_topic"]
+                .as_str()
+                .is_some_and(|cancel| match_topic(cancel, &action.topic).is_some())
+        {
+            log::debug!(
+                "sequence {stateindex} cancelled at step {}",
+                sequence["step"]
+            );
+            return SliceResult::state(json!({ stateindex: null, "_start": false }));
+        }
+
+        let triggered = match info["_topic"].as_str() {
+            Some(filter) => match_topic(filter, &action.topic).is_some(),
+            None => info["_start"] == json!(true),
+        };
+        if triggered && (!running || info["_restart"] != json!(false)) {
+            // Binary payloads are kept as hex, both keys are set to replace the previous trigger
+            let (payload, payload_hex) = match std::str::from_utf8(&action.payload) {
+                Ok(payload) => (json!(payload), json!(null)),
+                Err(_) => (json!(null), json!(hex::encode(&action.payload))),
+            };
+            sequence = json!({
+                "step": 0,
+                "resume_at": timestamp,
+                "trigger": {
+                    "topic": action.topic,
+                    "payload": payload,
+                    "payload_hex": payload_hex,
+                },
+            });
+        }
+        if sequence.is_null() || sequence["resume_at"].as_i64().unwrap_or(0) > timestamp {
+            return SliceResult::state(json!({ "_start": false }));
+        }
+
+        // Steps render with the action that triggered the sequence
+        let payload = match sequence["trigger"]["payload_hex"].as_str() {
+            Some(payload_hex) => hex::decode(payload_hex).unwrap_or_default(),
+            None => sequence["trigger"]["payload"]
+                .as_str()
+                .unwrap_or("")
+                .as_bytes()
+                .to_vec(),
+        };
+        let trigger = EngineAction::new(
+            sequence["trigger"]["topic"].as_str().unwrap_or("").into(),
+            payload,
+        );
+        let wildcards = info["_topic"]
+            .as_str()
+            .and_then(|filter| match_topic(filter, &trigger.topic))
+            .unwrap_or_default();
+        let context = TemplateContext {
+            info,
+            action: &trigger,
+            wildcards: &wildcards,
+        };
+
+        let mut messages = vec![];
+        let mut step = sequence["step"].as_u64().unwrap_or(0) as usize;
+        while let Some(definition) = steps.get(step) {
+            step += 1;
+            if let Some(topic) = definition["publish"].as_str() {
+                messages.push(context.render_message(
+                    topic,
+                    &definition["payload"],
+                    definition["json"] == json!(true),
+                    &definition["qos"],
+                    &definition["retain"],
+                ));
+            } else if let Some(millis) = definition["wait_millis"].as_i64() {
+                return SliceResult::new(
+                    json!({
+                        stateindex: {
+                            "step": step,
+                            "resume_at": timestamp + millis,
+                            "trigger": sequence["trigger"],
+                        },
+                        "_start": false,
+                    }),
+                    messages,
+                );
+            } else if let Some(source) = definition["condition"].as_str() {
+                let passed = match expression::evaluate(source, info, &trigger) {
+                    Ok(value) => truthy(&value),
+                    Err(error) => {
+                        log::warn!("sequence: {source}: {error}");
+                        false
+                    }
+                };
+                if !passed {
+                    return SliceResult::new(
+                        json!({ stateindex: null, "_start": false }),
+                        messages,
+                    );
+                }
+            } else {
+                log::warn!("sequence: unknown step {definition}");
+            }
+        }
+        SliceResult::new(json!({ stateindex: null, "_start": true }), messages)
+    })
+}
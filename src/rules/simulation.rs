@@ -0,0 +1,141 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+const WEEK_MILLIS: i64 = 7 * 24 * 3_600_000;
+// Replays missed while the engine was stopped are not sent in a burst
+const MAX_CATCH_UP_MILLIS: i64 = 60_000;
+
+// Pseudo random jitter, stable for an event in a given week
+fn jitter(at: i64, week: i64, max_jitter: i64) -> i64 {
+    if max_jitter <= 0 {
+        return 0;
+    }
+    let mut x = (at as u64) ^ (week as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    x ^= x >> 33;
+    x = x.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    x ^= x >> 33;
+    (x % (2 * max_jitter as u64 + 1)) as i64 - max_jitter
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _presence_simulation() -> (String, SliceFunction) {
+    (String::from("presence_simulation"), presence_simulation())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _presence_simulation_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("presence_simulation"),
+        ParameterSpec::default()
+            .required("_topics", ParameterType::Object)
+            .optional("_jitter_millis", ParameterType::Integer),
+    )
+}
+
+// Records the messages of the relays in _topics, {"state topic": "command topic"}, during
+// the last week while the household is home or night, only when the state changes. In
+// vacation mode the recorded week is replayed to the command topics, each message moved
+// up to _jitter_millis.
+pub fn presence_simulation() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topics) = info["_topics"].as_object() else {
+            log::warn!("presence_simulation: _topics required");
+            return SliceResult::empty();
+        };
+        let max_jitter = info["_jitter_millis"].as_i64().unwrap_or(900_000);
+        let stateindex = &format!("presence_simulation_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+        let mode = info["presence"]["mode"].as_str().unwrap_or("home");
+        let events = info[stateindex]["events"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        if mode == "vacation" {
+            let last = info[stateindex]["replayed"]
+                .as_i64()
+                .unwrap_or(timestamp)
+                .max(timestamp - MAX_CATCH_UP_MILLIS);
+            let mut messages = vec![];
+            for event in &events {
+                let (Some(at), Some(topic), Some(payload)) = (
+                    event["at"].as_i64(),
+                    event["topic"]
+                        .as_str()
+                        .and_then(|t| topics.get(t))
+                        .and_then(Value::as_str),
+                    event["payload"].as_str(),
+                ) else {
+                    continue;
+                };
+                let weeks = (timestamp - at) / WEEK_MILLIS;
+                let due = (weeks - 1..=weeks + 1)
+                    .filter(|week| *week >= 1)
+                    .any(|week| {
+                        let replay = at + week * WEEK_MILLIS + jitter(at, week, max_jitter);
+                        replay > last && replay <= timestamp
+                    });
+                if due {
+                    messages.push(EngineMessage::new(topic.into(), payload.into()));
+                }
+            }
+            return SliceResult::new(json!({ stateindex: { "replayed": timestamp } }), messages);
+        }
+
+        if !(mode == "home" || mode == "night") || !topics.contains_key(&action.topic) {
+            return SliceResult::state(json!({ stateindex: { "replayed": null } }));
+        }
+        // Relays report their state periodically, only the changes are recorded
+        let payload = String::from_utf8_lossy(&action.payload);
+        let last = events
+            .iter()
+            .rev()
+            .find(|event| event["topic"] == json!(action.topic));
+        if last.is_some_and(|event| event["payload"] == json!(payload)) {
+            return SliceResult::state(json!({ stateindex: { "replayed": null } }));
+        }
+        let mut events: Vec<Value> = events
+            .into_iter()
+            .filter(|event| {
+                event["at"]
+                    .as_i64()
+                    .is_some_and(|at| timestamp - at < WEEK_MILLIS)
+            })
+            .collect();
+        events.push(json!({
+            "at": timestamp,
+            "topic": action.topic,
+            "payload": payload,
+        }));
+        SliceResult::state(json!({
+            stateindex: {
+                "events": events,
+                "replayed": null,
+            }
+        }))
+    })
+}
@@ -20,14 +20,24 @@
 use linkme::distributed_slice;
 use serde_json::{json, Value};
 
-use crate::master::{EngineAction, SliceFunction, SliceResult};
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
 
-use super::SLICEFUNCTIONS;
+use super::template::match_topic;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
 
 #[distributed_slice(SLICEFUNCTIONS)]
 fn slice_start_action() -> (String, SliceFunction) {
     (String::from("start_action"), start_action())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn slice_start_action_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_action"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_command", ParameterType::String),
+    )
+}
 
 pub fn start_action() -> SliceFunction {
     Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
@@ -42,6 +52,16 @@ pub fn start_action() -> SliceFunction {
 fn slice_start_json_action() -> (String, SliceFunction) {
     (String::from("start_json_action"), start_json_action())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn slice_start_json_action_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_json_action"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_pointer", ParameterType::String)
+            .required("_value", ParameterType::Any),
+    )
+}
 
 pub fn start_json_action() -> SliceFunction {
     Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
@@ -61,7 +81,63 @@ pub fn imp_start_json_action(
 ) -> SliceResult {
     SliceResult::state(json!({ "_start" : action.matches(topic) && {
                 let json_payload = serde_json::from_slice(&action.payload).unwrap_or(json!(null));
-                json_payload.pointer(pointer).map_or(false, |v| v.eq(value))
+                json_payload.pointer(pointer).is_some_and(|v| v.eq(value))
             }
     }))
 }
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn slice_start_on_change() -> (String, SliceFunction) {
+    (String::from("start_on_change"), start_on_change())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn slice_start_on_change_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_on_change"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_from", ParameterType::Any)
+            .optional("_to", ParameterType::Any),
+    )
+}
+
+// Starts when the value at _pointer changes from the previous one received in the
+// same topic. _topic may contain wildcards. The first value of each topic is only recorded.
+pub fn start_on_change() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("start_on_change: _topic required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let pointer = info["_pointer"].as_str().unwrap_or("");
+        let stateindex = &format!("start_on_change_{}", info["_index"]);
+
+        if match_topic(topic, &action.topic).is_none() {
+            return SliceResult::state(json!({ "_start": false }));
+        }
+        let json_payload: Value = serde_json::from_slice(&action.payload)
+            .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload)));
+        let Some(new_value) = json_payload.pointer(pointer) else {
+            return SliceResult::state(json!({ "_start": false }));
+        };
+
+        // Values are kept as JSON text, merging objects would drop their null fields
+        // and keep the fields removed
+        let old_value = info[stateindex][&action.topic]
+            .as_str()
+            .and_then(|old_value| serde_json::from_str::<Value>(old_value).ok());
+        let changed = old_value.as_ref().is_some_and(|old_value| {
+            old_value != new_value
+                && (info["_from"].is_null() || &info["_from"] == old_value)
+                && (info["_to"].is_null() || &info["_to"] == new_value)
+        });
+
+        SliceResult::state(json!({
+            stateindex: { &action.topic: new_value.to_string() },
+            "_old_value": old_value,
+            "_new_value": new_value,
+            "_start": changed
+        }))
+    })
+}
@@ -21,9 +21,9 @@ use linkme::distributed_slice;
 use serde_json::json;
 
 use super::startaction::imp_start_json_action;
-use crate::master::{EngineAction, SliceFunction};
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction};
 
-use super::SLICEFUNCTIONS;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
 
 pub enum IkeaRemote {
     On,
@@ -42,6 +42,13 @@ fn start_ikea_remote_on() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::On),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_on_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_on"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_off() -> (String, SliceFunction) {
     (
@@ -49,6 +56,13 @@ fn start_ikea_remote_off() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::Off),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_off_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_off"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_toggle() -> (String, SliceFunction) {
     (
@@ -56,6 +70,13 @@ fn start_ikea_remote_toggle() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::Toggle),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_toggle_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_toggle"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_bright_down() -> (String, SliceFunction) {
     (
@@ -63,6 +84,13 @@ fn start_ikea_remote_bright_down() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::BrightDown),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_bright_down_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_bright_down"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_bright_up() -> (String, SliceFunction) {
     (
@@ -70,6 +98,13 @@ fn start_ikea_remote_bright_up() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::BrightUp),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_bright_up_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_bright_up"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_arrow_left() -> (String, SliceFunction) {
     (
@@ -77,6 +112,13 @@ fn start_ikea_remote_arrow_left() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::ArrowLeft),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_arrow_left_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_arrow_left"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 #[distributed_slice(SLICEFUNCTIONS)]
 fn start_ikea_remote_arrow_right() -> (String, SliceFunction) {
     (
@@ -84,6 +126,13 @@ fn start_ikea_remote_arrow_right() -> (String, SliceFunction) {
         start_ikea_remote(IkeaRemote::ArrowRight),
     )
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_ikea_remote_arrow_right_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_ikea_remote_arrow_right"),
+        ParameterSpec::default().required("_topic", ParameterType::String),
+    )
+}
 
 pub fn start_ikea_remote(command: IkeaRemote) -> SliceFunction {
     Box::new(move |info: &serde_json::Value, action: &EngineAction| {
@@ -0,0 +1,206 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use super::template::{match_topic, TemplateContext};
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::expression::{self, truthy, Context};
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+// A transition applies from the current state when "from" is missing, "*", the state
+// or a list containing the state.
+fn applies_from(transition: &Value, state: &str) -> bool {
+    match &transition["from"] {
+        Value::Null => true,
+        Value::String(from) => from == "*" || from == state,
+        Value::Array(from) => from.contains(&json!(state)),
+        _ => false,
+    }
+}
+
+// Returns the wildcards of the transition topic if all its triggers match
+fn triggers(
+    transition: &Value,
+    info: &Value,
+    action: &EngineAction,
+    state: &str,
+    elapsed: i64,
+) -> Option<Vec<String>> {
+    let wildcards = match transition["topic"].as_str() {
+        Some(filter) => {
+            let wildcards = match_topic(filter, &action.topic)?;
+            if !transition["payload"].is_null() {
+                let json_payload: Value = serde_json::from_slice(&action.payload)
+                    .unwrap_or_else(|_| json!(String::from_utf8_lossy(&action.payload)));
+                let pointer = transition["pointer"].as_str().unwrap_or("");
+                if json_payload.pointer(pointer) != Some(&transition["payload"]) {
+                    return None;
+                }
+            }
+            wildcards
+        }
+        None => vec![],
+    };
+    if let Some(after) = transition["after_millis"].as_i64() {
+        if elapsed < after {
+            return None;
+        }
+    }
+    if let Some(source) = transition["expression"].as_str() {
+        let inputs = json!({ "state": state, "elapsed": elapsed });
+        let result = expression::parse(source).and_then(|expr| {
+            expression::eval(&expr, &Context::new(info, action).with_inputs(inputs))
+        });
+        match result {
+            Ok(value) if truthy(&value) => {}
+            Ok(_) => return None,
+            Err(error) => {
+                log::warn!("state_machine: {source}: {error}");
+                return None;
+            }
+        }
+    } else if transition["topic"].is_null() && transition["after_millis"].is_null() {
+        // Transitions without triggers never fire
+        return None;
+    }
+    Some(wildcards)
+}
+
+fn run_actions(actions: &Value, context: &TemplateContext, messages: &mut Vec<EngineMessage>) {
+    for action in actions.as_array().into_iter().flatten() {
+        let Some(topic) = action["topic"].as_str() else {
+            log::warn!("state_machine: action without topic {action}");
+            continue;
+        };
+        messages.push(context.render_message(
+            topic,
+            &action["payload"],
+            action["json"] == json!(true),
+            &action["qos"],
+            &action["retain"],
+        ));
+    }
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _state_machine() -> (String, SliceFunction) {
+    (String::from("state_machine"), state_machine())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _state_machine_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("state_machine"),
+        ParameterSpec::default()
+            .required("_initial", ParameterType::String)
+            .required("_states", ParameterType::Object)
+            .required("_transitions", ParameterType::Array)
+            .optional("_statetopic", ParameterType::String),
+    )
+}
+
+// Finite state machine starting in _initial. _states are {"name": {"entry": [action],
+// "exit": [action]}} where actions are {"topic", "payload", "json", "qos", "retain"}
+// templates. _transitions are evaluated in order and the first one triggered moves to
+// its state: {"from": "state" | ["state"] | "*", "to": "state", "topic": "filter",
+// "payload": value, "pointer": "/value", "expression": "inputs.elapsed > 1000",
+// "after_millis": 60000, "actions": [action]}. Expressions get inputs.state and inputs.elapsed millis in
+// the state. The state is published retained in _statetopic and starts on change.
+pub fn state_machine() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let states = &info["_states"];
+        let stateindex = &format!("state_machine_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let mut messages = vec![];
+        let (current, since) = match info[stateindex]["state"].as_str() {
+            Some(state) => (
+                state.to_string(),
+                info[stateindex]["since"].as_i64().unwrap_or(timestamp),
+            ),
+            None => {
+                // First run enters the initial state
+                let Some(initial) = info["_initial"].as_str().map(String::from) else {
+                    log::warn!("state_machine: _initial state required");
+                    return SliceResult::empty();
+                };
+                let context = TemplateContext {
+                    info,
+                    action,
+                    wildcards: &[],
+                };
+                run_actions(&states[&initial]["entry"], &context, &mut messages);
+                (initial, timestamp)
+            }
+        };
+        let entered = info[stateindex]["state"].is_null();
+        let elapsed = timestamp - since;
+
+        let transition = info["_transitions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|transition| applies_from(transition, &current))
+            .find_map(|transition| {
+                let Some(to) = transition["to"].as_str() else {
+                    log::warn!("state_machine: transition without to state {transition}");
+                    return None;
+                };
+                triggers(transition, info, action, &current, elapsed)
+                    .map(|wildcards| (transition, to, wildcards))
+            });
+
+        let (next, since) = match transition {
+            Some((transition, to, wildcards)) => {
+                let next = to.to_string();
+                let context = TemplateContext {
+                    info,
+                    action,
+                    wildcards: &wildcards,
+                };
+                run_actions(&states[&current]["exit"], &context, &mut messages);
+                run_actions(&transition["actions"], &context, &mut messages);
+                run_actions(&states[&next]["entry"], &context, &mut messages);
+                (next, timestamp)
+            }
+            None => (current.clone(), since),
+        };
+
+        let changed = entered || next != current;
+        if changed {
+            if let Some(statetopic) = info["_statetopic"].as_str() {
+                let mut message = EngineMessage::new(statetopic.into(), next.clone().into_bytes());
+                message.properties = json!({ "retain": true });
+                messages.push(message);
+            }
+        }
+
+        SliceResult::new(
+            json!({
+                stateindex: { "state": next, "since": since },
+                "_state": next,
+                "_start": changed,
+            }),
+            messages,
+        )
+    })
+}
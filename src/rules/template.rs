@@ -0,0 +1,240 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+// Templates replace {{placeholder}} with values of the action being processed:
+// - {{payload}} the payload as text, {{payload:/pointer}} a value of the JSON payload.
+// - {{topic}} the topic, {{topic:N}} its segment N.
+// - {{wildcard:N}} the topic levels matched by the wildcard N of the topic filter.
+// - {{info:/pointer}} a value of info.
+// - {{timestamp}} the engine timestamp in millis, {{now}} or {{now:format}} the local time.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::Local;
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+// Returns the levels matched by each wildcard if the topic matches the MQTT filter
+pub fn match_topic(filter: &str, topic: &str) -> Option<Vec<String>> {
+    let mut wildcards = vec![];
+    let mut levels = topic.split('/');
+    for (i, part) in filter.split('/').enumerate() {
+        match part {
+            "#" => {
+                let rest: Vec<&str> = levels.collect();
+                wildcards.push(rest.join("/"));
+                // # also matches the parent level
+                return if i > 0 || !rest.is_empty() {
+                    Some(wildcards)
+                } else {
+                    None
+                };
+            }
+            "+" => wildcards.push(levels.next()?.to_string()),
+            level => {
+                if levels.next()? != level {
+                    return None;
+                }
+            }
+        }
+    }
+    match levels.next() {
+        None => Some(wildcards),
+        Some(_) => None,
+    }
+}
+
+pub struct TemplateContext<'a> {
+    pub info: &'a Value,
+    pub action: &'a EngineAction,
+    pub wildcards: &'a [String],
+}
+
+impl TemplateContext<'_> {
+    fn value(&self, placeholder: &str) -> Value {
+        let (name, argument) = match placeholder.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim())),
+            None => (placeholder, None),
+        };
+        match (name, argument) {
+            ("payload", None) => json!(String::from_utf8_lossy(&self.action.payload)),
+            ("payload", Some(pointer)) => serde_json::from_slice::<Value>(&self.action.payload)
+                .ok()
+                .and_then(|payload| payload.pointer(pointer).cloned())
+                .unwrap_or(Value::Null),
+            ("topic", None) => json!(self.action.topic),
+            ("topic", Some(index)) => index
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.action.topic.split('/').nth(i))
+                .map_or(Value::Null, |segment| json!(segment)),
+            ("wildcard", Some(index)) => index
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| self.wildcards.get(i))
+                .map_or(Value::Null, |wildcard| json!(wildcard)),
+            ("info", Some(pointer)) => self.info.pointer(pointer).cloned().unwrap_or(Value::Null),
+            ("timestamp", None) => self.info["_timestamp"].clone(),
+            ("now", None) => json!(Local::now().to_rfc3339()),
+            ("now", Some(format)) => {
+                // Invalid formats would panic when rendered
+                if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                    log::warn!("Invalid time format {format}");
+                    Value::Null
+                } else {
+                    json!(Local::now().format(format).to_string())
+                }
+            }
+            _ => {
+                log::warn!("Unknown template placeholder {placeholder}");
+                Value::Null
+            }
+        }
+    }
+
+    // Renders a text template
+    pub fn render(&self, template: &str) -> String {
+        let mut result = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            result.push_str(&rest[..start]);
+            match self.value(rest[start + 2..start + end].trim()) {
+                Value::Null => {}
+                Value::String(s) => result.push_str(&s),
+                value => result.push_str(&value.to_string()),
+            }
+            rest = &rest[start + end + 2..];
+        }
+        result.push_str(rest);
+        result
+    }
+
+    // Renders every string of a JSON template. A string that is a single placeholder
+    // is replaced by the value keeping its JSON type.
+    pub fn render_json(&self, template: &Value) -> Value {
+        match template {
+            Value::String(s) => {
+                let trimmed = s.trim();
+                match trimmed
+                    .strip_prefix("{{")
+                    .and_then(|t| t.strip_suffix("}}"))
+                    .filter(|placeholder| !placeholder.contains("{{"))
+                {
+                    Some(placeholder) => self.value(placeholder.trim()),
+                    None => json!(self.render(s)),
+                }
+            }
+            Value::Array(values) => values.iter().map(|v| self.render_json(v)).collect(),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), self.render_json(value)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    // Renders a message with a text or JSON payload template. Properties that are
+    // null keep the connection defaults.
+    pub fn render_message(
+        &self,
+        topic: &str,
+        payload: &Value,
+        json: bool,
+        qos: &Value,
+        retain: &Value,
+    ) -> EngineMessage {
+        let topic = self.render(topic);
+        let mut message = match payload {
+            Value::String(template) => {
+                let payload = self.render(template);
+                if json {
+                    // Text templates with JSON output must render valid JSON or a JSON string
+                    let value = serde_json::from_str::<Value>(&payload).unwrap_or(json!(payload));
+                    EngineMessage::new_json(topic, &value)
+                } else {
+                    EngineMessage::new(topic, payload.into_bytes())
+                }
+            }
+            template => EngineMessage::new_json(topic, &self.render_json(template)),
+        };
+        for (property, value) in [("qos", qos), ("retain", retain)] {
+            if !value.is_null() {
+                json_patch::merge(&mut message.properties, &json!({ property: value }));
+            }
+        }
+        message
+    }
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _publish_template() -> (String, SliceFunction) {
+    (String::from("publish_template"), publish_template())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _publish_template_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("publish_template"),
+        ParameterSpec::default()
+            .required("_forwardtopic", ParameterType::String)
+            .optional("_topic", ParameterType::String)
+            .optional("_payload", ParameterType::Any)
+            .optional("_json", ParameterType::Boolean)
+            .optional("_qos", ParameterType::Integer)
+            .optional("_retain", ParameterType::Boolean),
+    )
+}
+
+pub fn publish_template() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        // Without topic filter the message is published when _start
+        let wildcards = match info["_topic"].as_str() {
+            Some(filter) => match match_topic(filter, &action.topic) {
+                Some(wildcards) => wildcards,
+                None => return SliceResult::empty(),
+            },
+            None if info["_start"] == json!(true) => vec![],
+            None => return SliceResult::empty(),
+        };
+        let Some(forwardtopic) = info["_forwardtopic"].as_str() else {
+            log::warn!("publish_template: _forwardtopic required");
+            return SliceResult::empty();
+        };
+        let context = TemplateContext {
+            info,
+            action,
+            wildcards: &wildcards,
+        };
+        let message = context.render_message(
+            forwardtopic,
+            &info["_payload"],
+            info["_json"] == json!(true),
+            &info["_qos"],
+            &info["_retain"],
+        );
+        SliceResult::messages(vec![message])
+    })
+}
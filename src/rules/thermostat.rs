@@ -0,0 +1,211 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Datelike, Local, TimeZone, Timelike};
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{
+    EngineAction, EngineMessage, ParameterSpec, ParameterType, SliceFunction, SliceResult,
+};
+
+use super::timing::parse_time;
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const WEEK_MINUTES: i64 = 7 * 24 * 60;
+
+// The setpoint of the latest schedule entry started before the time. Entries are
+// {"days": ["mon", ...], "from": "hh:mm", "setpoint": 21}, without days they apply every day.
+pub fn schedule_setpoint(schedule: &Value, timestamp: i64) -> Option<f64> {
+    let now = Local.timestamp_millis_opt(timestamp).single()?;
+    let now_minute = now.weekday().num_days_from_monday() as i64 * 24 * 60
+        + now.hour() as i64 * 60
+        + now.minute() as i64;
+
+    let mut latest: Option<(i64, f64)> = None;
+    for entry in schedule.as_array()? {
+        let (Some(from), Some(setpoint)) = (
+            entry["from"].as_str().and_then(parse_time),
+            entry["setpoint"].as_f64(),
+        ) else {
+            continue;
+        };
+        for (day, name) in WEEKDAYS.iter().enumerate() {
+            let applies = match entry["days"].as_array() {
+                Some(days) => days.contains(&json!(name)),
+                None => true,
+            };
+            if applies {
+                // Minutes elapsed since the entry started, wrapping around the week
+                let start = day as i64 * 24 * 60 + from;
+                let elapsed = (now_minute - start).rem_euclid(WEEK_MINUTES);
+                if latest.is_none_or(|(latest, _)| elapsed < latest) {
+                    latest = Some((elapsed, setpoint));
+                }
+            }
+        }
+    }
+    latest.map(|(_, setpoint)| setpoint)
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _thermostat() -> (String, SliceFunction) {
+    (String::from("thermostat"), thermostat())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _thermostat_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("thermostat"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_forwardtopic", ParameterType::String)
+            .optional("_pointer", ParameterType::String)
+            .optional("_hysteresis", ParameterType::Number)
+            .optional("_min_on_millis", ParameterType::Integer)
+            .optional("_min_off_millis", ParameterType::Integer)
+            .optional("_max_age_millis", ParameterType::Integer)
+            .optional("_override_topic", ParameterType::String)
+            .optional("_schedule", ParameterType::Array)
+            .optional("_setpoint", ParameterType::Number)
+            .optional("_on", ParameterType::String)
+            .optional("_off", ParameterType::String)
+            .optional("_statetopic", ParameterType::String),
+    )
+}
+
+// Drives the heater relay in _forwardtopic from the temperature in _topic. The setpoint
+// follows _schedule, or _setpoint, unless overridden by a message in _override_topic,
+// {"setpoint": 23, "minutes": 120} or just the setpoint, "auto" cancels it. The relay
+// switches on at setpoint - _hysteresis and off at setpoint + _hysteresis, keeping it
+// at least _min_on_millis on and _min_off_millis off. Without temperature readings for
+// _max_age_millis the heater is switched off.
+pub fn thermostat() -> SliceFunction {
+    Box::new(|info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("thermostat: _topic required");
+            return SliceResult::empty();
+        };
+        let Some(forwardtopic) = info["_forwardtopic"].as_str() else {
+            log::warn!("thermostat: _forwardtopic required");
+            return SliceResult::empty();
+        };
+        let pointer = info["_pointer"].as_str().unwrap_or("/temperature");
+        let hysteresis = info["_hysteresis"].as_f64().unwrap_or(0.5);
+        let min_on_millis = info["_min_on_millis"].as_i64().unwrap_or(300_000);
+        let min_off_millis = info["_min_off_millis"].as_i64().unwrap_or(300_000);
+        let max_age_millis = info["_max_age_millis"].as_i64().unwrap_or(900_000);
+        let stateindex = &format!("thermostat_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        let mut state = info[stateindex].clone();
+        if state.is_null() {
+            state = json!({ "heating": false });
+        }
+        let json_payload: Value = serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+
+        if action.matches(topic) {
+            if let Some(temperature) = json_payload.pointer(pointer).and_then(Value::as_f64) {
+                state["temperature"] = json!(temperature);
+                state["read_at"] = json!(timestamp);
+            }
+        }
+        if info["_override_topic"]
+            .as_str()
+            .is_some_and(|override_topic| action.matches(override_topic))
+        {
+            let setpoint = json_payload["setpoint"]
+                .as_f64()
+                .or_else(|| json_payload.as_f64());
+            state["override"] = match setpoint {
+                Some(setpoint) => {
+                    let minutes = json_payload["minutes"].as_i64().unwrap_or(120);
+                    json!({ "setpoint": setpoint, "until": timestamp + minutes * 60_000 })
+                }
+                None => json!(null),
+            };
+        }
+        if state["override"]["until"]
+            .as_i64()
+            .is_some_and(|until| timestamp >= until)
+        {
+            state["override"] = json!(null);
+        }
+
+        let setpoint = state["override"]["setpoint"]
+            .as_f64()
+            .or_else(|| schedule_setpoint(&info["_schedule"], timestamp))
+            .or_else(|| info["_setpoint"].as_f64());
+        let heating = state["heating"] == json!(true);
+        let fresh = state["read_at"]
+            .as_i64()
+            .is_some_and(|read_at| timestamp - read_at <= max_age_millis);
+        let desired = match (setpoint, state["temperature"].as_f64()) {
+            (Some(setpoint), Some(temperature)) if fresh => {
+                if heating {
+                    temperature < setpoint + hysteresis
+                } else {
+                    temperature <= setpoint - hysteresis
+                }
+            }
+            _ => false,
+        };
+
+        let mut messages = vec![];
+        let min_millis = if heating {
+            min_on_millis
+        } else {
+            min_off_millis
+        };
+        let switch_allowed = state["changed_at"]
+            .as_i64()
+            .is_none_or(|changed_at| timestamp - changed_at >= min_millis);
+        // Losing the sensor turns the heater off without waiting
+        if desired != heating && (switch_allowed || !fresh) {
+            state["heating"] = json!(desired);
+            state["changed_at"] = json!(timestamp);
+            let payload = if desired {
+                info["_on"].as_str().unwrap_or("on")
+            } else {
+                info["_off"].as_str().unwrap_or("off")
+            };
+            messages.push(EngineMessage::new(forwardtopic.into(), payload.into()));
+        }
+
+        let setpoint = setpoint.map_or(json!(null), |setpoint| json!(setpoint));
+        if let Some(statetopic) = info["_statetopic"].as_str() {
+            if !messages.is_empty() || state["setpoint"] != setpoint {
+                let mut message = EngineMessage::new_json(
+                    statetopic.into(),
+                    &json!({
+                        "temperature": state["temperature"],
+                        "setpoint": setpoint,
+                        "heating": state["heating"],
+                        "override": !state["override"].is_null(),
+                    }),
+                );
+                message.properties = json!({ "retain": true });
+                messages.push(message);
+            }
+        }
+        state["setpoint"] = setpoint;
+
+        SliceResult::new(json!({ stateindex: state }), messages)
+    })
+}
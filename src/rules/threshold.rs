@@ -0,0 +1,144 @@
+//    MyRulesIoT is a rules engine for MQTT
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use linkme::distributed_slice;
+use serde_json::{json, Value};
+
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
+
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+
+pub enum Threshold {
+    Above,
+    Below,
+    Between,
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn start_threshold_above() -> (String, SliceFunction) {
+    (
+        String::from("start_threshold_above"),
+        start_threshold(Threshold::Above),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_threshold_above_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_threshold_above"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_pointer", ParameterType::String)
+            .required("_value", ParameterType::Number)
+            .optional("_hysteresis", ParameterType::Number)
+            .optional("_edge", ParameterType::Boolean),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn start_threshold_below() -> (String, SliceFunction) {
+    (
+        String::from("start_threshold_below"),
+        start_threshold(Threshold::Below),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_threshold_below_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_threshold_below"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_pointer", ParameterType::String)
+            .required("_value", ParameterType::Number)
+            .optional("_hysteresis", ParameterType::Number)
+            .optional("_edge", ParameterType::Boolean),
+    )
+}
+#[distributed_slice(SLICEFUNCTIONS)]
+fn start_threshold_between() -> (String, SliceFunction) {
+    (
+        String::from("start_threshold_between"),
+        start_threshold(Threshold::Between),
+    )
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn start_threshold_between_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("start_threshold_between"),
+        ParameterSpec::default()
+            .required("_topic", ParameterType::String)
+            .required("_pointer", ParameterType::String)
+            .required("_min", ParameterType::Number)
+            .required("_max", ParameterType::Number)
+            .optional("_hysteresis", ParameterType::Number)
+            .optional("_edge", ParameterType::Boolean),
+    )
+}
+
+// Activates when the value at _pointer crosses the threshold and deactivates only once
+// it goes back beyond the _hysteresis band. With _edge (default) _start is set only on
+// activation, otherwise on every message while active.
+pub fn start_threshold(threshold: Threshold) -> SliceFunction {
+    Box::new(move |info: &Value, action: &EngineAction| -> SliceResult {
+        let Some(topic) = info["_topic"].as_str() else {
+            log::warn!("start_threshold: _topic required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let Some(pointer) = info["_pointer"].as_str() else {
+            log::warn!("start_threshold: _pointer required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+        let hysteresis = info["_hysteresis"].as_f64().unwrap_or(0.0);
+        let edge = info["_edge"].as_bool().unwrap_or(true);
+        let stateindex = &format!("start_threshold_{}", info["_index"]);
+        let limits = match threshold {
+            Threshold::Above | Threshold::Below => info["_value"].as_f64().map(|v| (v, v)),
+            Threshold::Between => info["_min"].as_f64().zip(info["_max"].as_f64()),
+        };
+        let Some((min, max)) = limits else {
+            log::warn!("start_threshold: _value or _min and _max required");
+            return SliceResult::state(json!({ "_start": false }));
+        };
+
+        if !action.matches(topic) {
+            return SliceResult::state(json!({ "_start": false }));
+        }
+        let json_payload: Value = serde_json::from_slice(&action.payload).unwrap_or(json!(null));
+        let Some(value) = json_payload.pointer(pointer).and_then(Value::as_f64) else {
+            return SliceResult::state(json!({ "_start": false }));
+        };
+
+        let was_active = info[stateindex]["active"] == json!(true);
+        let active = match threshold {
+            Threshold::Above => value > min || (was_active && value >= min - hysteresis),
+            Threshold::Below => value < max || (was_active && value <= max + hysteresis),
+            Threshold::Between => {
+                (value > min && value < max)
+                    || (was_active && value >= min - hysteresis && value <= max + hysteresis)
+            }
+        };
+
+        SliceResult::state(json!({
+            stateindex: {
+                "active": active,
+                "value": value,
+            },
+            "_threshold_value": value,
+            "_start": active && !(edge && was_active)
+        }))
+    })
+}
@@ -20,13 +20,26 @@
 use linkme::distributed_slice;
 use serde_json::{json, Value};
 
-use super::SLICEFUNCTIONS;
-use crate::master::{EngineAction, SliceFunction, SliceResult};
+use super::{SLICEFUNCTIONS, SLICEPARAMETERS};
+use crate::master::{EngineAction, ParameterSpec, ParameterType, SliceFunction, SliceResult};
+
+// Minutes of the day of a "hh:mm" time
+pub fn parse_time(time: &str) -> Option<i64> {
+    let (hours, minutes) = time.split_once(':')?;
+    Some(hours.parse::<i64>().ok()? * 60 + minutes.parse::<i64>().ok()?)
+}
 
 #[distributed_slice(SLICEFUNCTIONS)]
 fn _condition_sleep() -> (String, SliceFunction) {
     (String::from("condition_sleep"), condition_sleep())
 }
+#[distributed_slice(SLICEPARAMETERS)]
+fn _condition_sleep_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("condition_sleep"),
+        ParameterSpec::default().optional("_millis", ParameterType::Integer),
+    )
+}
 pub fn condition_sleep() -> SliceFunction {
     Box::new(|info: &Value, _action: &EngineAction| -> SliceResult {
         let millis = info["_millis"].as_i64().unwrap_or(1000);
@@ -49,8 +62,175 @@ pub fn condition_sleep() -> SliceFunction {
             }
         }
 
-        return SliceResult::state(json!({
+        SliceResult::state(json!({
+            "_start": null
+        }))
+    })
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _condition_debounce() -> (String, SliceFunction) {
+    (String::from("condition_debounce"), condition_debounce())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _condition_debounce_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("condition_debounce"),
+        ParameterSpec::default()
+            .optional("_millis", ParameterType::Integer)
+            .optional("_leading", ParameterType::Boolean)
+            .optional("_trailing", ParameterType::Boolean),
+    )
+}
+
+// Starts once the _start activations stop for _millis (trailing edge) and/or
+// on the first activation of a burst (leading edge)
+pub fn condition_debounce() -> SliceFunction {
+    Box::new(|info: &Value, _action: &EngineAction| -> SliceResult {
+        let millis = info["_millis"].as_i64().unwrap_or(1000);
+        let leading = info["_leading"].as_bool().unwrap_or(false);
+        let trailing = info["_trailing"].as_bool().unwrap_or(true);
+        let stateindex = &format!("condition_debounce_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+        let last = info[stateindex]["last"].as_i64();
+
+        if info["_start"] == json!(true) {
+            let in_burst = last.is_some_and(|last| timestamp - last <= millis);
+            let fire = leading && !in_burst;
+            return SliceResult::state(json!({
+                stateindex: {
+                    "last": timestamp,
+                    "pending": trailing && !fire,
+                },
+                "_start": if fire { json!(true) } else { json!(null) }
+            }));
+        }
+
+        if let Some(last) = last {
+            if timestamp - last > millis {
+                return SliceResult::state(json!({
+                    stateindex: null,
+                    "_start": if info[stateindex]["pending"] == json!(true) { json!(true) } else { json!(null) }
+                }));
+            }
+        }
+
+        SliceResult::state(json!({
+            "_start": null
+        }))
+    })
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _condition_throttle() -> (String, SliceFunction) {
+    (String::from("condition_throttle"), condition_throttle())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _condition_throttle_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("condition_throttle"),
+        ParameterSpec::default()
+            .optional("_millis", ParameterType::Integer)
+            .optional("_leading", ParameterType::Boolean)
+            .optional("_trailing", ParameterType::Boolean),
+    )
+}
+
+// Starts at most once every _millis, on the first activation (leading edge)
+// and/or at the end of the window if there were more activations (trailing edge)
+pub fn condition_throttle() -> SliceFunction {
+    Box::new(|info: &Value, _action: &EngineAction| -> SliceResult {
+        let millis = info["_millis"].as_i64().unwrap_or(1000);
+        let leading = info["_leading"].as_bool().unwrap_or(true);
+        let trailing = info["_trailing"].as_bool().unwrap_or(false);
+        let stateindex = &format!("condition_throttle_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+        let window = info[stateindex]["window"]
+            .as_i64()
+            .filter(|window| timestamp - window < millis);
+        let pending = info[stateindex]["pending"] == json!(true);
+
+        if info["_start"] == json!(true) {
+            return match window {
+                None => SliceResult::state(json!({
+                    stateindex: {
+                        "window": timestamp,
+                        "pending": trailing && !leading,
+                    },
+                    "_start": if leading { json!(true) } else { json!(null) }
+                })),
+                Some(window) => SliceResult::state(json!({
+                    stateindex: {
+                        "window": window,
+                        "pending": pending || trailing,
+                    },
+                    "_start": null
+                })),
+            };
+        }
+
+        if window.is_none() && !info[stateindex].is_null() {
+            // The trailing activation opens a new window
+            return SliceResult::state(json!({
+                stateindex: if pending { json!({ "window": timestamp, "pending": false }) } else { json!(null) },
+                "_start": if pending { json!(true) } else { json!(null) }
+            }));
+        }
+
+        SliceResult::state(json!({
             "_start": null
-        }));
+        }))
+    })
+}
+
+#[distributed_slice(SLICEFUNCTIONS)]
+fn _condition_rate_limit() -> (String, SliceFunction) {
+    (String::from("condition_rate_limit"), condition_rate_limit())
+}
+#[distributed_slice(SLICEPARAMETERS)]
+fn _condition_rate_limit_parameters() -> (String, ParameterSpec) {
+    (
+        String::from("condition_rate_limit"),
+        ParameterSpec::default()
+            .optional("_millis", ParameterType::Integer)
+            .optional("_count", ParameterType::Integer),
+    )
+}
+
+// Allows at most _count activations in any period of _millis, the rest are discarded
+pub fn condition_rate_limit() -> SliceFunction {
+    Box::new(|info: &Value, _action: &EngineAction| -> SliceResult {
+        let millis = info["_millis"].as_i64().unwrap_or(1000);
+        let count = info["_count"].as_u64().unwrap_or(1) as usize;
+        let stateindex = &format!("condition_rate_limit_{}", info["_index"]);
+        let timestamp = info["_timestamp"].as_i64().unwrap();
+
+        if info["_start"] != json!(true) {
+            return SliceResult::state(json!({
+                "_start": null
+            }));
+        }
+
+        let mut activations: Vec<i64> = info[stateindex]
+            .as_array()
+            .map(|activations| {
+                activations
+                    .iter()
+                    .filter_map(Value::as_i64)
+                    .filter(|activation| timestamp - activation < millis)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if activations.len() >= count {
+            return SliceResult::state(json!({
+                stateindex: activations,
+                "_start": null
+            }));
+        }
+        activations.push(timestamp);
+        SliceResult::state(json!({
+            stateindex: activations,
+            "_start": true
+        }))
     })
 }
@@ -28,6 +28,8 @@ where
 {
     fn reduce(&self, state: S, action: A) -> (S, R);
     fn is_final(&self, state: &S) -> bool;
+    // Saves the state while running, engines without storage do nothing
+    fn persist(&self, _state: &S) {}
 }
 
 pub async fn task_runtime_loop<A, R, S, E>(
@@ -51,6 +53,7 @@ where
 
         log::debug!("Persist state {:?} and result {:?}.", &state, &result);
 
+        engine.persist(&state);
         let is_final = engine.is_final(&state);
 
         tx.send(result).await.unwrap();
@@ -17,9 +17,36 @@
 //    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
 //
 
+mod alarm;
+mod authorization;
+mod batch;
+mod counter;
+mod cover;
+mod dryrun;
+mod expression;
+mod history;
 mod ikea;
 mod jsontests;
+mod light;
+mod logic;
 mod masterintegration;
+mod motion;
+mod onchange;
+mod persistence;
+mod plugins;
+mod presence;
+mod remote;
 mod savelist;
+mod sequence;
+mod scripting;
+mod shadow;
+mod simulation;
+mod statemachine;
+mod template;
+mod thermostat;
+mod threshold;
+mod timing;
+mod wasm;
 
+mod reducetester;
 mod runtimetester;
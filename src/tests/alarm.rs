@@ -0,0 +1,187 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use crate::master::EngineAction;
+
+use super::reducetester::ReduceTester;
+
+const SECOND: i64 = 1_000;
+
+#[test]
+fn alarm_states() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "alarm",
+        "_keypad_topic": "alarm/keypad",
+        "_codes": ["1234", "sha256:fe2592b42a727e977f055947385b709cc82b16b9a87f88c6abf3900d65d0cdc3"],
+        "_statetopic": "alarm/state",
+        "_sirentopic": "alarm/siren",
+        "_notifytopic": "alarm/audit",
+        "_exit_delay_millis": 30 * SECOND,
+        "_entry_delay_millis": 20 * SECOND,
+        "_trigger_millis": 60 * SECOND,
+        "_sensors": {
+            "door": {"topic": "zigbee2mqtt/door", "pointer": "/contact", "value": false, "entry": true},
+            "window": {"topic": "zigbee2mqtt/window", "pointer": "/contact", "value": false},
+            "hall": {"topic": "zigbee2mqtt/hall", "pointer": "/occupancy", "value": true, "modes": ["armed_away"]}
+        }
+    }]));
+    let mut send = |topic: &str, payload: Value, timestamp: i64| -> Vec<String> {
+        tester
+            .reduce(&EngineAction::new_json(topic.into(), payload), timestamp)
+            .iter()
+            .map(|m| {
+                let mut payload = m
+                    .payload_into_json()
+                    .unwrap_or_else(|_| json!(String::from_utf8_lossy(&m.payload)));
+                if let Some(event) = payload.as_object_mut() {
+                    event.remove("timestamp");
+                }
+                format!("{} {}", m.topic, payload)
+            })
+            .collect()
+    };
+
+    assert_eq!(
+        vec![
+            r#"alarm/state "arming""#,
+            r#"alarm/audit {"event":"bypass","sensors":["window"]}"#,
+            r#"alarm/audit {"event":"state","from":"disarmed","state":"arming"}"#
+        ],
+        send(
+            "alarm/keypad",
+            json!({"action": "arm_away", "bypass": ["window"]}),
+            0
+        )
+    );
+    assert!(send("SYSMR/action/tick", json!(null), 29 * SECOND).is_empty());
+    assert_eq!(
+        r#"alarm/state "armed_away""#,
+        send("SYSMR/action/tick", json!(null), 30 * SECOND)[0]
+    );
+
+    // Bypassed sensor
+    assert!(send("zigbee2mqtt/window", json!({"contact": false}), 40 * SECOND).is_empty());
+    // Entry delay then instant sensor
+    assert_eq!(
+        r#"alarm/state "pending""#,
+        send("zigbee2mqtt/door", json!({"contact": false}), 50 * SECOND)[0]
+    );
+    assert_eq!(
+        vec![
+            r#"alarm/state "triggered""#,
+            r#"alarm/siren "on""#,
+            r#"alarm/audit {"event":"sensor","sensor":"hall"}"#,
+            r#"alarm/audit {"event":"state","from":"pending","state":"triggered"}"#
+        ],
+        send("zigbee2mqtt/hall", json!({"occupancy": true}), 55 * SECOND)
+    );
+
+    assert_eq!(
+        vec![r#"alarm/audit {"command":"disarm","event":"invalid_code"}"#],
+        send(
+            "alarm/keypad",
+            json!({"action": "disarm", "code": "0000"}),
+            60 * SECOND
+        )
+    );
+    assert_eq!(
+        vec![
+            r#"alarm/state "disarmed""#,
+            r#"alarm/siren "off""#,
+            r#"alarm/audit {"event":"state","from":"triggered","state":"disarmed"}"#
+        ],
+        send(
+            "alarm/keypad",
+            json!({"action": "disarm", "code": "4321"}),
+            70 * SECOND
+        )
+    );
+    assert!(send("zigbee2mqtt/hall", json!({"occupancy": true}), 80 * SECOND).is_empty());
+}
+
+#[test]
+fn alarm_trigger_timeout() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "alarm",
+        "_keypad_topic": "alarm/keypad",
+        "_codes": ["1234"],
+        "_statetopic": "alarm/state",
+        "_exit_delay_millis": 0,
+        "_trigger_millis": 60 * SECOND,
+        "_sensors": {
+            "hall": {"topic": "zigbee2mqtt/hall", "pointer": "/occupancy", "value": true, "modes": ["armed_away"]}
+        }
+    }]));
+    let mut send =
+        |topic: &str, payload: Value, timestamp: i64| tester.send_json(topic, payload, timestamp);
+
+    assert_eq!(
+        vec!["alarm/state armed_home"],
+        send("alarm/keypad", json!({"action": "arm_home"}), 0)
+    );
+    // Not watched when armed home
+    assert!(send("zigbee2mqtt/hall", json!({"occupancy": true}), 10 * SECOND).is_empty());
+    assert_eq!(
+        vec!["alarm/state disarmed"],
+        send(
+            "alarm/keypad",
+            json!({"action": "disarm", "code": "1234"}),
+            20 * SECOND
+        )
+    );
+    assert_eq!(
+        vec!["alarm/state armed_away"],
+        send("alarm/keypad", json!({"action": "arm_away"}), 30 * SECOND)
+    );
+    assert_eq!(
+        vec!["alarm/state triggered"],
+        send("zigbee2mqtt/hall", json!({"occupancy": true}), 40 * SECOND)
+    );
+    assert_eq!(
+        vec!["alarm/state armed_away"],
+        send("SYSMR/action/tick", json!(null), 100 * SECOND)
+    );
+}
+
+#[test]
+fn alarm_sensor_without_topic() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "alarm",
+        "_keypad_topic": "alarm/keypad",
+        "_statetopic": "alarm/state",
+        "_exit_delay_millis": 0,
+        "_sensors": {
+            "door": {"pointer": "/contact", "value": false},
+            "window": {"topic": "zigbee2mqtt/window", "pointer": "/contact", "value": false}
+        }
+    }]));
+
+    assert_eq!(
+        vec!["alarm/state armed_away"],
+        tester.send_json("alarm/keypad", json!({"action": "arm_away"}), 0)
+    );
+    // The sensor without topic is skipped and the others still trigger
+    assert_eq!(
+        vec!["alarm/state triggered"],
+        tester.send_json("zigbee2mqtt/window", json!({"contact": false}), SECOND)
+    );
+    assert_eq!(json!("triggered"), tester.info["alarm_0"]["state"]);
+}
@@ -0,0 +1,295 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{
+    sign, Authorization, AuthorizationKey, EngineAction, EngineState, MasterEngine, Permission,
+};
+use crate::rules;
+use crate::runtime::Engine;
+
+fn authorized_engine() -> MasterEngine {
+    MasterEngine::new(
+        String::from("MYRULESTEST"),
+        rules::distributed_engine_functions(),
+    )
+    .with_authorization(Authorization::new(vec![
+        AuthorizationKey {
+            id: String::from("viewer"),
+            token: Some(String::from("viewertoken")),
+            secret: None,
+            permissions: vec![Permission::Read],
+        },
+        AuthorizationKey {
+            id: String::from("admin"),
+            token: Some(String::from("admintoken")),
+            secret: Some(String::from("adminsecret")),
+            permissions: vec![Permission::Read, Permission::Edit, Permission::Lifecycle],
+        },
+    ]))
+}
+
+#[test]
+fn token_commands() {
+    let engine = authorized_engine();
+
+    // Command without envelope
+    let (state, result) = engine.reduce(
+        EngineState::new_functions(vec![]),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({"name": "relay_on", "_topic": "relay/command"}),
+        ),
+    );
+    assert_eq!(result.messages.len(), 1);
+    assert_eq!(&result.messages[0].topic, "MYRULESTEST/notify/system_error");
+    assert_eq!(state.functions.len(), 0);
+
+    // Read only key cannot edit
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({
+                "key": "viewer",
+                "token": "viewertoken",
+                "payload": {"name": "relay_on", "_topic": "relay/command"}
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "command": "functions_push",
+            "error": "Key viewer has not Edit permission"
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(state.functions.len(), 0);
+
+    // Admin key edits
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({
+                "key": "admin",
+                "token": "admintoken",
+                "payload": {"name": "relay_on", "_topic": "relay/command"}
+            }),
+        ),
+    );
+    assert_eq!(
+        &result.messages[0].topic,
+        "MYRULESTEST/notify/functions_push"
+    );
+    assert_eq!(state.functions.len(), 1);
+
+    // Wrong token
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_clear".into(),
+            json!({
+                "key": "admin",
+                "token": "viewertoken",
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "command": "functions_clear",
+            "error": "Invalid token"
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(state.functions.len(), 1);
+
+    // Read only key reads
+    let (_, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_getall".into(),
+            json!({
+                "key": "viewer",
+                "token": "viewertoken",
+            }),
+        ),
+    );
+    assert_eq!(
+        json!([{"name": "relay_on", "_topic": "relay/command"}]),
+        result.messages[0].payload_into_json().unwrap()
+    );
+}
+
+#[test]
+fn signature_commands() {
+    let engine = authorized_engine();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let topic = "MYRULESTEST/command/exit";
+
+    // Expired signature
+    let (state, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            topic.into(),
+            json!({
+                "key": "admin",
+                "timestamp": timestamp - 600_000,
+                "signature": sign("adminsecret", topic, timestamp - 600_000, br#""reboot""#),
+                "payload": "reboot"
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "command": "exit",
+            "error": "Signature timestamp out of range"
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert!(!engine.is_final(&state));
+
+    // Signature of a different payload
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            topic.into(),
+            json!({
+                "key": "admin",
+                "timestamp": timestamp,
+                "signature": sign("adminsecret", topic, timestamp, br#""upgrade""#),
+                "payload": "reboot"
+            }),
+        ),
+    );
+    assert_eq!(
+        json!("Invalid signature"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+    assert!(!engine.is_final(&state));
+
+    // Valid signature
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            topic.into(),
+            json!({
+                "key": "admin",
+                "timestamp": timestamp,
+                "signature": sign("adminsecret", topic, timestamp, br#""reboot""#),
+                "payload": "reboot"
+            }),
+        ),
+    );
+    assert_eq!(result.messages.len(), 0);
+    assert!(engine.is_final(&state));
+    assert_eq!(
+        json!({"FINAL": ["NORMAL", "reboot"]}),
+        serde_json::to_value(&state.engine_status).unwrap()
+    );
+}
+
+#[test]
+fn not_command_actions() {
+    let engine = authorized_engine();
+
+    let (_, result) = engine.reduce(
+        EngineState::new_functions(vec![serde_json::from_value(json!({
+            "name": "forward_user_action",
+            "_topic": "source_topic",
+            "_forwardtopic": "target_topic"
+        }))
+        .unwrap()]),
+        EngineAction::new("source_topic".into(), b"hello".into()),
+    );
+    assert_eq!(result.messages.len(), 1);
+    assert_eq!(&result.messages[0].topic, "target_topic");
+    assert_eq!(result.messages[0].payload, b"hello");
+}
+
+#[test]
+fn signature_raw_payload_and_replay() {
+    let engine = authorized_engine();
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let topic = "MYRULESTEST/command/functions_push";
+
+    // Signed over the payload text as sent, key order and spacing included
+    let payload = r#"{ "_topic": "relay/command",  "name": "relay_on" }"#;
+    let signature = sign("adminsecret", topic, timestamp, payload.as_bytes());
+    let envelope = format!(
+        r#"{{"key": "admin", "timestamp": {timestamp}, "signature": "{signature}", "payload": {payload}}}"#
+    );
+
+    let (state, result) = engine.reduce(
+        EngineState::new_functions(vec![]),
+        EngineAction::new(topic.into(), envelope.clone().into_bytes()),
+    );
+    assert_eq!(
+        &result.messages[0].topic,
+        "MYRULESTEST/notify/functions_push"
+    );
+    assert_eq!(state.functions.len(), 1);
+
+    // The same signed command cannot be sent again
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new(topic.into(), envelope.into_bytes()),
+    );
+    assert_eq!(
+        json!("Signature already used"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+    assert_eq!(state.functions.len(), 1);
+}
+
+#[test]
+fn secret_is_not_a_token() {
+    let engine = authorized_engine();
+    let command = |key: &str, token: &str| {
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_getall".into(),
+            json!({ "key": key, "token": token }),
+        )
+    };
+
+    // The signing secret is never accepted as a bearer token
+    let (state, result) = engine.reduce(EngineState::default(), command("admin", "adminsecret"));
+    assert_eq!(
+        json!("Invalid token"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+
+    // Keys configured with the secret as token only accept signatures
+    let engine = MasterEngine::new(
+        String::from("MYRULESTEST"),
+        rules::distributed_engine_functions(),
+    )
+    .with_authorization(Authorization::new(vec![AuthorizationKey {
+        id: String::from("shared"),
+        token: Some(String::from("sharedsecret")),
+        secret: Some(String::from("sharedsecret")),
+        permissions: vec![Permission::Read],
+    }]));
+    let (_, result) = engine.reduce(state, command("shared", "sharedsecret"));
+    assert_eq!(
+        json!("Invalid token"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+}
@@ -0,0 +1,152 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{EngineAction, EngineState, MasterEngine};
+use crate::rules;
+use crate::runtime::Engine;
+
+fn initial_state() -> EngineState {
+    EngineState::new_functions(
+        serde_json::from_value(json!([
+            {"name": "start_action", "_topic": "button", "_command": "press"},
+            {"name": "relay_on", "_topic": "relay1/command"},
+            {"name": "relay_off", "_topic": "relay2/command"}
+        ]))
+        .unwrap(),
+    )
+}
+
+#[test]
+fn batch_commit() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, result) = engine.reduce(
+        initial_state(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_batch".into(),
+            json!([
+                {"op": "remove", "index": 2},
+                {"op": "replace", "index": 1, "function": {"name": "relay_off", "_topic": "relay1/command"}},
+                {"op": "add", "function": {"name": "relay_on", "_topic": "relay3/command"}},
+                {"op": "move", "from": 2, "to": 1}
+            ]),
+        ),
+    );
+
+    assert_eq!(
+        &result.messages[0].topic,
+        "MYRULESTEST/notify/functions_batch"
+    );
+    assert_eq!(
+        json!({
+            "success": true,
+            "results": [
+                {"op": "remove", "success": true, "index": 2, "function": "relay_off"},
+                {"op": "replace", "success": true, "index": 1, "function": "relay_off"},
+                {"op": "add", "success": true, "index": 2, "function": "relay_on"},
+                {"op": "move", "success": true, "from": 2, "to": 1, "function": "relay_on"}
+            ]
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(
+        json!([
+            {"name": "start_action", "_topic": "button", "_command": "press"},
+            {"name": "relay_on", "_topic": "relay3/command"},
+            {"name": "relay_off", "_topic": "relay1/command"}
+        ]),
+        serde_json::to_value(&state.functions).unwrap()
+    );
+}
+
+#[test]
+fn batch_rollback() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, result) = engine.reduce(
+        initial_state(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_batch".into(),
+            json!([
+                {"op": "remove", "index": 0},
+                {"op": "add", "function": {"name": "not_a_function"}},
+                {"op": "remove", "index": 5}
+            ]),
+        ),
+    );
+
+    assert_eq!(
+        json!({
+            "success": false,
+            "results": [
+                {"op": "remove", "success": true, "index": 0, "function": "start_action"},
+                {"op": "add", "success": false, "error": "Function not found: not_a_function"},
+                {"op": "remove", "success": false, "error": "Index 5 out of range"}
+            ]
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(
+        serde_json::to_value(&initial_state().functions).unwrap(),
+        serde_json::to_value(&state.functions).unwrap()
+    );
+}
+
+#[test]
+fn batch_moves_state() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+    let functions = json!([
+        {"name": "relay_on", "_topic": "opened"},
+        {"name": "start_on_change", "_topic": "door"},
+        {"name": "condition_debounce", "_millis": 1000}
+    ]);
+    let state = EngineState::new(
+        json!({
+            "start_on_change_1": {"door": "\"closed\""},
+            "condition_debounce_2": {"last": 0},
+            "sensor_7": "kept"
+        }),
+        serde_json::from_value(functions).unwrap(),
+    );
+
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_batch".into(),
+            json!([
+                {"op": "remove", "index": 2},
+                {"op": "move", "from": 1, "to": 0}
+            ]),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "start_on_change_0": {"door": "\"closed\""},
+            "sensor_7": "kept"
+        }),
+        state.info
+    );
+
+    // The moved function still knows the previous value and starts on change
+    let (_, result) = engine.reduce(state, EngineAction::new("door".into(), "\"open\"".into()));
+    assert_eq!(1, result.messages.len());
+    assert_eq!("opened", result.messages[0].topic);
+}
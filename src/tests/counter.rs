@@ -0,0 +1,165 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Local, TimeZone};
+use serde_json::{json, Value};
+
+use crate::master::EngineAction;
+
+use super::reducetester::ReduceTester;
+
+const MINUTE: i64 = 60_000;
+
+fn local(month: u32, day: u32, hour: u32) -> i64 {
+    Local
+        .with_ymd_and_hms(2026, month, day, hour, 0, 0)
+        .unwrap()
+        .timestamp_millis()
+}
+
+#[test]
+fn counter_daily_reset() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "counter",
+        "_topic": "zigbee2mqtt/button",
+        "_pointer": "/action",
+        "_payload": "single",
+        "_forwardtopic": "stats/button",
+        "_publish_millis": 60 * MINUTE
+    }]));
+    let start = local(10, 19, 10);
+    let button = "zigbee2mqtt/button";
+
+    assert_eq!(
+        vec![r#"stats/button {"period":"2026-10-19","previous":null,"value":1}"#],
+        tester.send_json(button, json!({"action": "single"}), start)
+    );
+    assert!(tester
+        .send_json(button, json!({"action": "double"}), start + MINUTE)
+        .is_empty());
+    assert!(tester
+        .send_json(button, json!({"action": "single"}), start + 2 * MINUTE)
+        .is_empty());
+    assert_eq!(json!(2), tester.info["counter_0"]["value"]);
+    assert_eq!(
+        vec![r#"stats/button {"period":"2026-10-19","previous":null,"value":2}"#],
+        tester.tick(start + 60 * MINUTE)
+    );
+
+    // The period restarts once, also when the info is restored after a restart
+    tester.restart();
+    assert_eq!(
+        vec![r#"stats/button {"period":"2026-10-20","previous":2,"value":0}"#],
+        tester.tick(local(10, 20, 0))
+    );
+    assert!(tester.tick(local(10, 20, 0) + 250).is_empty());
+    assert_eq!(json!(0), tester.info["counter_0"]["value"]);
+}
+
+#[test]
+fn accumulator_cumulative_meter() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "accumulator",
+        "_topic": "water/meter",
+        "_pointer": "/liters",
+        "_cumulative": true,
+        "_reset": "monthly"
+    }]));
+    let meter = "water/meter";
+
+    tester.send_json(meter, json!({"liters": 1000}), local(10, 31, 10));
+    tester.send_json(meter, json!({"liters": 1010}), local(10, 31, 11));
+    // Retained reading received again after a reconnection
+    tester.send_json(meter, json!({"liters": 1010}), local(10, 31, 12));
+    // Meter reset
+    tester.send_json(meter, json!({"liters": 5}), local(10, 31, 13));
+    assert_eq!(json!(15.0), tester.info["accumulator_0"]["value"]);
+
+    tester.send_json(meter, json!({"liters": 8}), local(11, 1, 10));
+    assert_eq!(json!(3.0), tester.info["accumulator_0"]["value"]);
+    assert_eq!(json!(15.0), tester.info["accumulator_0"]["previous"]);
+    assert_eq!(json!("2026-11"), tester.info["accumulator_0"]["period"]);
+}
+
+#[test]
+fn integrator_energy() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "integrator",
+        "_topic": "zigbee2mqtt/plug",
+        "_pointer": "/power",
+        "_max_gap_millis": 10 * MINUTE
+    }]));
+    let start = local(10, 19, 10);
+    let plug = "zigbee2mqtt/plug";
+    let energy = |info: &Value| info["integrator_0"]["value"].as_f64().unwrap();
+
+    tester.send_json(plug, json!({"power": 1000}), start);
+    for minute in 1..=60 {
+        tester.send_json(plug, json!({"power": 1000}), start + minute * MINUTE);
+    }
+    assert!((energy(&tester.info) - 1.0).abs() < 1e-9);
+
+    // Readings are held at most _max_gap_millis
+    tester.send_json(plug, json!({"power": 600}), start + 60 * MINUTE);
+    tester.tick(start + 65 * MINUTE);
+    tester.restart();
+    tester.tick(start + 120 * MINUTE);
+    tester.tick(start + 121 * MINUTE);
+    assert!((energy(&tester.info) - 1.1).abs() < 1e-9);
+}
+
+#[test]
+fn integrator_split_at_midnight() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "integrator",
+        "_topic": "zigbee2mqtt/plug",
+        "_pointer": "/power",
+        "_max_gap_millis": 120 * MINUTE
+    }]));
+
+    tester.send_json(
+        "zigbee2mqtt/plug",
+        json!({"power": 1000}),
+        local(10, 19, 23) + 30 * MINUTE,
+    );
+    tester.tick(local(10, 20, 0) + 30 * MINUTE);
+    // Each day gets the half hour before and after midnight
+    let state = &tester.info["integrator_0"];
+    assert!((state["previous"].as_f64().unwrap() - 0.5).abs() < 1e-9);
+    assert!((state["value"].as_f64().unwrap() - 0.5).abs() < 1e-9);
+    assert_eq!(json!("2026-10-20"), state["period"]);
+}
+
+#[test]
+fn counter_ignores_retained() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "counter", "_topic": "zigbee2mqtt/button"},
+        {"name": "counter"}
+    ]));
+    let start = local(10, 19, 10);
+    let mut retained = EngineAction::new("zigbee2mqtt/button".into(), b"{}".to_vec());
+    retained.retain = true;
+
+    tester.send("zigbee2mqtt/button", "{}", start);
+    // Sent again by the broker after a reconnection
+    tester.reduce(&retained, start + MINUTE);
+    assert_eq!(json!(1), tester.info["counter_0"]["value"]);
+    // Without _topic the counter is skipped
+    assert!(tester.info["counter_1"].is_null());
+}
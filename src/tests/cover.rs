@@ -0,0 +1,137 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn cover_position_tracking() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "cover",
+        "_topic": "home/blind/set",
+        "_forwardtopic": "shellies/blind/roller/0/command",
+        "_up": "open",
+        "_down": "close",
+        "_statetopic": "home/blind/state",
+        "_travel_millis": 10_000
+    }]));
+    let mut send =
+        |topic: &str, payload: &str, timestamp: i64| tester.send(topic, payload, timestamp);
+
+    // Unknown position, closing runs the full travel
+    assert_eq!(
+        vec![
+            "shellies/blind/roller/0/command close",
+            r#"home/blind/state {"position":100.0,"state":"closing"}"#
+        ],
+        send("home/blind/set", "close", 0)
+    );
+    assert!(send("SYSMR/action/tick", "", 5_000).is_empty());
+    assert_eq!(
+        vec![
+            "shellies/blind/roller/0/command stop",
+            r#"home/blind/state {"position":0.0,"state":"stopped"}"#
+        ],
+        send("SYSMR/action/tick", "", 10_000)
+    );
+
+    assert_eq!(
+        "shellies/blind/roller/0/command open",
+        send("home/blind/set", r#"{"position": 40}"#, 20_000)[0]
+    );
+    assert!(send("SYSMR/action/tick", "", 23_750).is_empty());
+    assert_eq!(
+        vec![
+            "shellies/blind/roller/0/command stop",
+            r#"home/blind/state {"position":40.0,"state":"stopped"}"#
+        ],
+        send("SYSMR/action/tick", "", 24_000)
+    );
+
+    assert_eq!(
+        "shellies/blind/roller/0/command open",
+        send("home/blind/set", "open", 30_000)[0]
+    );
+    assert_eq!(
+        vec![
+            "shellies/blind/roller/0/command stop",
+            r#"home/blind/state {"position":70.0,"state":"stopped"}"#
+        ],
+        send("home/blind/set", "stop", 33_000)
+    );
+    assert!(send("SYSMR/action/tick", "", 40_000).is_empty());
+    assert_eq!(json!({"position": 70.0}), tester.info["cover_0"]);
+}
+
+#[test]
+fn cover_reverse_stops_first() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "cover",
+        "_topic": "home/blind/set",
+        "_forwardtopic": "shellies/blind/roller/0/command",
+        "_travel_millis": 10_000,
+        "_reverse_delay_millis": 1_000
+    }]))
+    .with_info(json!({"cover_0": {"position": 0.0}}));
+
+    assert_eq!(
+        vec!["shellies/blind/roller/0/command up"],
+        tester.send("home/blind/set", "open", 0)
+    );
+    // Reversing at 40 stops and waits before closing
+    assert_eq!(
+        vec!["shellies/blind/roller/0/command stop"],
+        tester.send("home/blind/set", "close", 4_000)
+    );
+    assert!(tester.tick(4_500).is_empty());
+    assert_eq!(
+        vec!["shellies/blind/roller/0/command down"],
+        tester.tick(5_000)
+    );
+    // The position did not change while stopped
+    assert!(tester.tick(8_500).is_empty());
+    assert_eq!(
+        vec!["shellies/blind/roller/0/command stop"],
+        tester.tick(9_000)
+    );
+    assert_eq!(json!({"position": 0.0}), tester.info["cover_0"]);
+
+    // Without delay both commands are sent at once
+    let mut tester = ReduceTester::new(json!([{
+        "name": "cover",
+        "_topic": "home/blind/set",
+        "_forwardtopic": "shellies/blind/roller/0/command",
+        "_travel_millis": 10_000,
+        "_reverse_delay_millis": 0
+    }]))
+    .with_info(json!({"cover_0": {"position": 0.0}}));
+    tester.send("home/blind/set", "open", 0);
+    assert_eq!(
+        vec![
+            "shellies/blind/roller/0/command stop",
+            "shellies/blind/roller/0/command down"
+        ],
+        tester.send("home/blind/set", "close", 4_000)
+    );
+    assert_eq!(
+        vec!["shellies/blind/roller/0/command stop"],
+        tester.tick(8_000)
+    );
+}
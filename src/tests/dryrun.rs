@@ -0,0 +1,164 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{EngineAction, EngineState, MasterEngine};
+use crate::rules;
+use crate::runtime::Engine;
+
+#[test]
+fn dryrun_replay() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_dryrun".into(),
+            json!({
+                "functions": [
+                    {"name": "forward_action", "_topic": "remote", "_forwardtopic": "lamp"}
+                ],
+                "actions": [
+                    {"topic": "remote", "payload": {"action": "toggle"}, "timestamp": 1000},
+                    {"topic": "remote", "payload": {"action": "toggle"}, "timestamp": 2000}
+                ]
+            }),
+        ),
+    );
+
+    assert_eq!(
+        &result.messages[0].topic,
+        "MYRULESTEST/notify/functions_dryrun"
+    );
+    assert_eq!(
+        json!({
+            "success": true,
+            "errors": [],
+            "results": [{
+                "topic": "remote",
+                "messages": [{"topic": "lamp", "payload": "\u{1}", "properties": null}],
+                "info": {"lamp": true}
+            }, {
+                "topic": "remote",
+                "messages": [{"topic": "lamp", "payload": "\u{0}", "properties": null}],
+                "info": {"lamp": false}
+            }]
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    // Live state not touched
+    assert_eq!(json!({}), state.info);
+    assert_eq!(state.functions.len(), 0);
+}
+
+#[test]
+fn dryrun_invalid() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions())
+        .with_parameter_specs(rules::distributed_parameter_specs());
+
+    let (_, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_dryrun".into(),
+            json!({
+                "functions": [
+                    {"name": "relay_on", "_topic": "lamp"},
+                    {"name": "not_a_function"}
+                ]
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "success": false,
+            "errors": [{"index": 1, "function": "not_a_function", "error": "Function not found: not_a_function"}],
+            "results": []
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    // Missing _forwardtopic parameter
+    let (_, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_dryrun".into(),
+            json!({
+                "functions": [
+                    {"name": "relay_on", "_topic": "lamp"},
+                    {"name": "forward_user_action", "_topic": "remote"}
+                ],
+                "actions": [{"topic": "remote", "payload": "hello"}]
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "success": false,
+            "errors": [{
+                "index": 1,
+                "function": "forward_user_action",
+                "error": "Missing parameter _forwardtopic"
+            }],
+            "results": []
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    // Parameters are checked without actions to replay
+    let (_, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_dryrun".into(),
+            json!({
+                "functions": [
+                    {"name": "start_threshold_above", "_topic": "sensor", "_pointer": "/temperature", "_value": "25"},
+                    {"name": "condition_sleep", "_millis": 1000, "_modes": "away"}
+                ]
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "success": false,
+            "errors": [{
+                "index": 0,
+                "function": "start_threshold_above",
+                "error": "Parameter _value must be Number"
+            }, {
+                "index": 1,
+                "function": "condition_sleep",
+                "error": "Parameter _modes must be Array of String"
+            }],
+            "results": []
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+}
+
+#[test]
+fn parameter_specs_cover_functions() {
+    let specs = rules::distributed_parameter_specs();
+    let mut names: Vec<String> = rules::distributed_engine_functions().into_keys().collect();
+    names.sort();
+    let mut specified: Vec<String> = specs.into_keys().collect();
+    specified.sort();
+    assert_eq!(names, specified);
+}
@@ -0,0 +1,150 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::expression::evaluate;
+use crate::master::EngineAction;
+use crate::rules::expression::{publish_expression, start_expression};
+
+#[test]
+fn expressions() {
+    let info = json!({
+        "_timestamp": 1000,
+        "mode": "night",
+        "lamp": {"brightness": 120}
+    });
+    let action = EngineAction::new_json(
+        "zigbee2mqtt/livingroom/sensor".into(),
+        json!({"temperature": 21.5, "humidity": 72, "tags": ["a", "b"]}),
+    );
+
+    let cases = [
+        ("1 + 2 * 3", json!(7)),
+        ("(1 + 2) * 3", json!(9)),
+        ("7 % 4 - -1", json!(4)),
+        ("payload.humidity > 70 && info.mode == 'night'", json!(true)),
+        ("payload.temperature >= 22 || !true", json!(false)),
+        (
+            "segments[1] + '/' + segments[-1]",
+            json!("livingroom/sensor"),
+        ),
+        ("payload['tags'][1]", json!("b")),
+        ("payload.missing.value", json!(null)),
+        (
+            "upper(info.mode) + str(info.lamp.brightness / 2)",
+            json!("NIGHT60"),
+        ),
+        ("contains(payload.tags, 'a') ? 'yes' : 'no'", json!("yes")),
+        ("round(payload.temperature) == 22", json!(true)),
+        ("max(1, payload.humidity, 5)", json!(72)),
+        ("coalesce(payload.pressure, 1013)", json!(1013)),
+        ("timestamp + 1", json!(1001)),
+        (
+            "starts_with(topic, 'zigbee2mqtt/') && len(segments) == 3",
+            json!(true),
+        ),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(
+            expected,
+            evaluate(source, &info, &action).unwrap(),
+            "{source}"
+        );
+    }
+
+    let errors = [
+        ("1 +", "Syntax error: Unexpected token End"),
+        ("unknown + 1", "Unknown variable or function unknown"),
+        ("system('ls')", "Unknown variable or function system"),
+        ("1 / 0", "Type error: Division by zero"),
+        ("len(1, 2)", "Wrong number of arguments for len"),
+        ("a = 1", "Syntax error: Unexpected character ="),
+    ];
+    for (source, expected) in errors {
+        assert_eq!(
+            expected,
+            evaluate(source, &info, &action).unwrap_err().to_string(),
+            "{source}"
+        );
+    }
+
+    let deep = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+    assert_eq!(
+        "Syntax error: Expression too deep",
+        evaluate(&deep, &info, &action).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn expression_functions() {
+    let start = start_expression();
+    let publish = publish_expression();
+
+    let action = EngineAction::new_json("sensor/humidity".into(), json!({"value": 75}));
+
+    let result = start(
+        &json!({
+            "_topic": "sensor/humidity",
+            "_expression": "payload.value > 70"
+        }),
+        &action,
+    );
+    assert_eq!(json!({"_start": true}), result.state);
+
+    let result = start(
+        &json!({
+            "_topic": "sensor/temperature",
+            "_expression": "payload.value > 70"
+        }),
+        &action,
+    );
+    assert_eq!(json!({"_start": false}), result.state);
+
+    let result = publish(
+        &json!({
+            "_start": true,
+            "_topic": "fan/set",
+            "_expression": "payload.value > 80 ? 'high' : 'low'"
+        }),
+        &action,
+    );
+    assert_eq!(result.messages[0].topic, "fan/set");
+    assert_eq!(result.messages[0].payload, b"low");
+
+    let result = publish(
+        &json!({
+            "_start": true,
+            "_topic": "fan/state",
+            "_expression": "payload.value / 100"
+        }),
+        &action,
+    );
+    assert_eq!(result.messages[0].payload, b"0.75");
+
+    let result = publish(
+        &json!({
+            "_start": false,
+            "_topic": "fan/set",
+            "_expression": "'on'"
+        }),
+        &action,
+    );
+    assert!(result.messages.is_empty());
+}
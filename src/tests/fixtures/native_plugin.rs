@@ -0,0 +1,67 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+
+// Native plugin built by the plugins tests as a cdylib, without dependencies.
+// MYRULESIOT_VERSION is the engine version at build time.
+
+use std::ffi::{c_char, CStr, CString};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+static MANIFEST: OnceLock<CString> = OnceLock::new();
+static FREES: AtomicUsize = AtomicUsize::new(0);
+
+#[no_mangle]
+pub extern "C" fn myrulesiot_plugin_manifest() -> *const c_char {
+    MANIFEST
+        .get_or_init(|| {
+            CString::new(format!(
+                r#"{{"name":"test","version":"1.0.0","abi":1,"engine":"{}","functions":["native_echo","native_fail"]}}"#,
+                env!("MYRULESIOT_VERSION")
+            ))
+            .unwrap()
+        })
+        .as_ptr()
+}
+
+// native_echo publishes its input in native/out, native_fail fails
+#[no_mangle]
+pub extern "C" fn myrulesiot_plugin_call(
+    function: *const c_char,
+    input: *const c_char,
+) -> *mut c_char {
+    let function = unsafe { CStr::from_ptr(function) }.to_string_lossy();
+    let input = unsafe { CStr::from_ptr(input) }.to_string_lossy();
+    match function.as_ref() {
+        "native_echo" => CString::new(format!(
+            r#"{{"messages":[{{"topic":"native/out","payload":{input}}}],"state":{{"frees":{}}}}}"#,
+            FREES.load(Ordering::SeqCst)
+        ))
+        .unwrap()
+        .into_raw(),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn myrulesiot_plugin_free(result: *mut c_char) {
+    FREES.fetch_add(1, Ordering::SeqCst);
+    drop(unsafe { CString::from_raw(result) });
+}
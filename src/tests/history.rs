@@ -0,0 +1,183 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{EngineAction, EngineState, MasterEngine};
+use crate::rules;
+use crate::runtime::Engine;
+
+#[test]
+fn history_rollback() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions())
+        .with_history_limit(3);
+
+    let (state, _) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({"name": "relay_on", "_topic": "relay1/command"}),
+        ),
+    );
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({"name": "relay_off", "_topic": "relay2/command"}),
+        ),
+    );
+    // Not edit commands are not recorded
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_getall".into(), vec![]),
+    );
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_clear".into(), vec![]),
+    );
+
+    // Initial revision discarded because of the history limit
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_history".into(), vec![]),
+    );
+    let revisions = result.messages[0].payload_into_json().unwrap();
+    assert_eq!(
+        &result.messages[0].topic,
+        "MYRULESTEST/notify/functions_history"
+    );
+    assert_eq!(
+        vec![1, 2, 3],
+        revisions
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["revision"].as_i64().unwrap())
+            .collect::<Vec<i64>>()
+    );
+    assert_eq!(json!("functions_clear"), revisions[2]["command"]);
+    assert_eq!(json!(0), revisions[2]["count"]);
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_diff".into(),
+            json!({"from": 1, "to": 2}),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "from": 1,
+            "to": 2,
+            "diff": [{"op": "add", "path": "/1", "value": {"name": "relay_off", "_topic": "relay2/command"}}]
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_rollback".into(),
+            json!({"revision": 2}),
+        ),
+    );
+    assert_eq!(
+        json!({"success": true, "revision": 4}),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(state.functions.len(), 2);
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_rollback".into(),
+            json!({"revision": 0}),
+        ),
+    );
+    assert_eq!(&result.messages[0].topic, "MYRULESTEST/notify/system_error");
+    assert_eq!(state.functions.len(), 2);
+}
+
+#[test]
+fn history_metadata_without_authorization() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, _) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_push".into(),
+            json!({
+                "author": "adrian",
+                "correlation": "deploy-42",
+                "payload": {"name": "relay_on", "_topic": "relay1/command"}
+            }),
+        ),
+    );
+    assert_eq!(state.functions.len(), 1);
+    assert_eq!("relay_on", state.functions[0].name());
+
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_clear".into(), vec![]),
+    );
+    let (_, result) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_history".into(), vec![]),
+    );
+    let revisions = result.messages[0].payload_into_json().unwrap();
+    assert_eq!(json!("adrian"), revisions[1]["author"]);
+    assert_eq!(json!("deploy-42"), revisions[1]["correlation"]);
+    assert_eq!(json!(null), revisions[1]["key"]);
+    assert_eq!(json!(null), revisions[2]["author"]);
+}
+
+#[test]
+fn rollback_moves_state() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, _) = engine.reduce(
+        EngineState::new(
+            json!({"start_on_change_0": {"door": "\"closed\""}}),
+            serde_json::from_value(json!([{"name": "start_on_change", "_topic": "door"}])).unwrap(),
+        ),
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_putall".into(),
+            json!([
+                {"name": "relay_on", "_topic": "opened"},
+                {"name": "start_on_change", "_topic": "door"}
+            ]),
+        ),
+    );
+    assert_eq!(
+        json!({"start_on_change_1": {"door": "\"closed\""}}),
+        state.info
+    );
+
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_rollback".into(),
+            json!({"revision": 0}),
+        ),
+    );
+    assert_eq!(
+        json!({"start_on_change_0": {"door": "\"closed\""}}),
+        state.info
+    );
+}
@@ -0,0 +1,115 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use crate::master::EngineAction;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn light_dimming_with_ikea_remote() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_ikea_remote_bright_up", "_topic": "zigbee2mqtt/remote"},
+        {"name": "light_step_up", "_topic": "zigbee2mqtt/bulb", "_step": 100, "_transition": 0.5},
+        {"name": "start_ikea_remote_bright_down", "_topic": "zigbee2mqtt/remote"},
+        {"name": "light_step_down", "_topic": "zigbee2mqtt/bulb", "_step": 100},
+        {"name": "start_ikea_remote_arrow_left", "_topic": "zigbee2mqtt/remote"},
+        {"name": "light_color_temp", "_topic": "zigbee2mqtt/bulb", "_color_temp": 370},
+        {"name": "start_ikea_remote_arrow_right", "_topic": "zigbee2mqtt/remote"},
+        {"name": "light_scene", "_topic": "zigbee2mqtt/bulb", "_scene": 2}
+    ]));
+
+    let mut send = |topic: &str, payload: Value| -> Vec<Value> {
+        tester
+            .reduce(&EngineAction::new_json(topic.into(), payload), 0)
+            .iter()
+            .map(|m| {
+                assert_eq!("zigbee2mqtt/bulb/set", m.topic);
+                m.payload_into_json().unwrap()
+            })
+            .collect()
+    };
+
+    assert!(send(
+        "zigbee2mqtt/bulb",
+        json!({"state": "ON", "brightness": 100, "linkquality": 80})
+    )
+    .is_empty());
+    assert_eq!(
+        vec![json!({"state": "ON", "brightness": 200, "transition": 0.5})],
+        send(
+            "zigbee2mqtt/remote",
+            json!({"action": "brightness_up_click"})
+        )
+    );
+    assert_eq!(
+        vec![json!({"state": "ON", "brightness": 254, "transition": 0.5})],
+        send(
+            "zigbee2mqtt/remote",
+            json!({"action": "brightness_up_click"})
+        )
+    );
+    assert_eq!(
+        vec![json!({"brightness": 154})],
+        send(
+            "zigbee2mqtt/remote",
+            json!({"action": "brightness_down_click"})
+        )
+    );
+    assert_eq!(
+        vec![json!({"brightness": 54})],
+        send(
+            "zigbee2mqtt/remote",
+            json!({"action": "brightness_down_click"})
+        )
+    );
+    assert_eq!(
+        vec![json!({"state": "OFF"})],
+        send(
+            "zigbee2mqtt/remote",
+            json!({"action": "brightness_down_click"})
+        )
+    );
+    assert_eq!(
+        vec![json!({"color_temp": 370})],
+        send("zigbee2mqtt/remote", json!({"action": "arrow_left_click"}))
+    );
+    assert_eq!(
+        vec![json!({"scene_recall": 2})],
+        send("zigbee2mqtt/remote", json!({"action": "arrow_right_click"}))
+    );
+}
+
+#[test]
+fn light_missing_parameters() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_action", "_topic": "button", "_command": "press"},
+        {"name": "light_set_level", "_topic": "zigbee2mqtt/bulb"},
+        {"name": "light_color_temp", "_topic": "zigbee2mqtt/bulb"},
+        {"name": "light_scene", "_topic": "zigbee2mqtt/bulb"},
+        {"name": "light_set_level", "_topic": "zigbee2mqtt/bulb", "_percent": 50}
+    ]));
+
+    // Commands without their value are skipped
+    assert_eq!(
+        vec![r#"zigbee2mqtt/bulb/set {"brightness":127,"state":"ON"}"#],
+        tester.send("button", "press", 0)
+    );
+}
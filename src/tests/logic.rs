@@ -0,0 +1,97 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn condition_logic_transitions() {
+    let mut tester = ReduceTester::new(json!([
+        {
+            "name": "condition_logic",
+            "_inputs": {
+                "motion": {"topic": "zigbee2mqtt/hall_motion", "pointer": "/occupancy"},
+                "lux": {"topic": "zigbee2mqtt/hall_motion", "pointer": "/illuminance_lux"},
+                "tv": "livingroom/tv/state"
+            },
+            "_expression": "inputs.motion && inputs.lux < 30 && inputs.tv != 'on'"
+        },
+        {"name": "relay_on", "_topic": "hall/light"}
+    ]));
+
+    let actions = [
+        ("livingroom/tv/state", json!("on")),
+        (
+            "zigbee2mqtt/hall_motion",
+            json!({"occupancy": true, "illuminance_lux": 10}),
+        ),
+        ("livingroom/tv/state", json!("off")),
+        (
+            "zigbee2mqtt/hall_motion",
+            json!({"occupancy": true, "illuminance_lux": 12}),
+        ),
+        ("other/topic", json!(1)),
+        (
+            "zigbee2mqtt/hall_motion",
+            json!({"occupancy": false, "illuminance_lux": 12}),
+        ),
+        (
+            "zigbee2mqtt/hall_motion",
+            json!({"occupancy": true, "illuminance_lux": 50}),
+        ),
+        (
+            "zigbee2mqtt/hall_motion",
+            json!({"occupancy": true, "illuminance_lux": 20}),
+        ),
+    ];
+    let mut published = vec![];
+    for (topic, payload) in actions {
+        published.push(tester.send_json(topic, payload, 0).len());
+    }
+    // Starts only on the transitions to true
+    assert_eq!(vec![0, 0, 1, 0, 0, 0, 0, 1], published);
+    assert_eq!(
+        json!({"motion": "true", "lux": "20", "tv": "\"off\""}),
+        tester.info["condition_logic_0"]["inputs"]
+    );
+}
+
+#[test]
+fn condition_logic_object_inputs() {
+    let mut tester = ReduceTester::new(json!([
+        {
+            "name": "condition_logic",
+            "_inputs": {"lamp": "zigbee2mqtt/lamp"},
+            "_expression": "inputs.lamp.brightness > 50"
+        },
+        {"name": "relay_on", "_topic": "bright"}
+    ]));
+
+    let mut published = vec![];
+    for payload in [
+        json!({"state": "ON", "color": null, "brightness": 100}),
+        json!({"state": "ON", "color": null}),
+        json!({"state": "ON", "color": null, "brightness": 100}),
+    ] {
+        published.push(tester.send_json("zigbee2mqtt/lamp", payload, 0).len());
+    }
+    // The brightness removed from the payload is not kept
+    assert_eq!(vec![1, 0, 1], published);
+}
@@ -19,6 +19,7 @@
 
 use serde_json::json;
 
+use super::reducetester::ReduceTester;
 use super::runtimetester::RuntimeTester;
 use crate::master::{EngineAction, EngineStatus, FinalStatus};
 
@@ -100,3 +101,23 @@ async fn basic_messages() {
 
     assert!(testengine.recv().await.is_none());
 }
+
+#[test]
+fn parameters_scope() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_action", "_topic": "button", "_command": "press"},
+        {"name": "publish_template", "_forwardtopic": "first", "_payload": "1", "_retain": true},
+        {"name": "publish_template", "_forwardtopic": "second", "_payload": "2"}
+    ]));
+
+    let messages = tester.reduce(&EngineAction::new("button".into(), b"press".to_vec()), 0);
+
+    // _start set by the first function result chains to the next ones
+    assert_eq!(2, messages.len());
+    assert_eq!(json!({"retain": true}), messages[0].properties);
+    // _retain of the previous function does not apply
+    assert_eq!("second", messages[1].topic);
+    assert_eq!(json!(null), messages[1].properties);
+    // Non persistent keys are removed at the end
+    assert_eq!(json!({}), tester.info);
+}
@@ -0,0 +1,66 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn motion_light_timeout_and_guards() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "motion_light",
+        "_topic": "zigbee2mqtt/hall_motion",
+        "_forwardtopic": "shellies/hall/relay/0/command",
+        "_millis": 60_000,
+        "_max_illuminance": 100,
+        "_manual_topic": "hall/switch",
+        "_override_millis": 600_000
+    }]));
+    let motion = |lux: i64| json!({"occupancy": true, "illuminance_lux": lux});
+
+    // Too bright, nothing happens
+    assert!(tester
+        .send_json("zigbee2mqtt/hall_motion", motion(300), 0)
+        .is_empty());
+    assert_eq!(
+        vec!["shellies/hall/relay/0/command on"],
+        tester.send_json("zigbee2mqtt/hall_motion", motion(20), 1_000)
+    );
+    // Retrigger extends the timeout, also when the light itself made it brighter
+    assert!(tester
+        .send_json("zigbee2mqtt/hall_motion", motion(300), 40_000)
+        .is_empty());
+    assert!(tester.tick(80_000).is_empty());
+    assert_eq!(
+        vec!["shellies/hall/relay/0/command off"],
+        tester.tick(100_000)
+    );
+    assert_eq!(json!(null), tester.info["motion_light_0"]);
+
+    // Manual override ignores motion for a while
+    assert!(tester.send("hall/switch", "on", 200_000).is_empty());
+    assert!(tester
+        .send_json("zigbee2mqtt/hall_motion", motion(20), 210_000)
+        .is_empty());
+    assert!(tester.tick(700_000).is_empty());
+    assert_eq!(
+        vec!["shellies/hall/relay/0/command off"],
+        tester.tick(800_000)
+    );
+}
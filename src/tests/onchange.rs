@@ -0,0 +1,82 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn start_on_change_per_topic() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_on_change", "_topic": "zigbee2mqtt/+", "_pointer": "/contact"},
+        {"name": "publish_template", "_forwardtopic": "changed/{{topic:1}}", "_payload": "{{info:/_old_value}}>{{info:/_new_value}}"},
+        {"name": "start_on_change", "_topic": "zigbee2mqtt/+", "_pointer": "/contact", "_from": true, "_to": false},
+        {"name": "relay_on", "_topic": "opened"}
+    ]));
+
+    let mut published = vec![];
+    for (device, contact) in [
+        ("door", true),
+        ("window", false),
+        ("door", true),
+        ("door", false),
+        ("window", true),
+        ("door", true),
+    ] {
+        published.extend(tester.send_json(
+            &format!("zigbee2mqtt/{device}"),
+            json!({ "contact": contact, "battery": 90 }),
+            0,
+        ));
+    }
+    assert_eq!(
+        vec![
+            "changed/door true>false",
+            "opened on",
+            "changed/window false>true",
+            "changed/door false>true"
+        ],
+        published
+    );
+    assert_eq!(
+        json!({"zigbee2mqtt/door": "true", "zigbee2mqtt/window": "true"}),
+        tester.info["start_on_change_0"]
+    );
+}
+
+#[test]
+fn start_on_change_objects() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_on_change", "_topic": "zigbee2mqtt/lamp"},
+        {"name": "relay_on", "_topic": "changed"}
+    ]));
+
+    let mut changes = vec![];
+    for payload in [
+        json!({"state": "ON", "color": null, "brightness": 100}),
+        json!({"state": "ON", "color": null, "brightness": 100}),
+        json!({"state": "ON", "color": null}),
+        json!({"state": "ON", "color": null}),
+        json!({"state": "ON", "color": {"x": 0.3}}),
+        json!({"state": "ON", "color": {"x": 0.3}}),
+    ] {
+        changes.push(!tester.send_json("zigbee2mqtt/lamp", payload, 0).is_empty());
+    }
+    assert_eq!(vec![false, false, true, false, true, false], changes);
+}
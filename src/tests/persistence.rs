@@ -0,0 +1,114 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::master::{EngineState, Persistence, ReducerFunction, StateFiles};
+
+fn state_files(test: &str) -> StateFiles {
+    let directory = std::env::temp_dir().join(format!("myrulesiot-{test}-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    StateFiles {
+        functions: directory.join("engine_functions.json"),
+        history: directory.join("engine_history.json"),
+        scripts: directory.join("engine_scripts.json"),
+        info: directory.join("engine_info.json"),
+        journal: directory.join("engine_journal.json"),
+    }
+}
+
+fn temporary(path: &Path) -> PathBuf {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    temporary.into()
+}
+
+fn read(path: &PathBuf) -> serde_json::Value {
+    serde_json::from_slice(&fs::read(path).unwrap()).unwrap()
+}
+
+#[test]
+fn persist_every_interval() {
+    let files = state_files("persist");
+    let persistence = Persistence::new(files.clone(), 10_000);
+    let mut state = EngineState::default();
+
+    persistence.persist(&state, 0);
+    assert_eq!(json!([]), read(&files.functions));
+    assert!(files.history.exists());
+    assert!(files.scripts.exists());
+
+    // Not saved again until the interval elapses
+    let function: ReducerFunction =
+        serde_json::from_value(json!({"name": "relay_on", "_topic": "relay/command"})).unwrap();
+    state.functions.push(function);
+    persistence.persist(&state, 5_000);
+    assert_eq!(json!([]), read(&files.functions));
+
+    persistence.persist(&state, 10_000);
+    assert_eq!(
+        json!([{"name": "relay_on", "_topic": "relay/command"}]),
+        read(&files.functions)
+    );
+    assert!(!temporary(&files.functions).exists());
+    assert!(!files.journal.exists());
+
+    // The functions state in info is saved too
+    state.info = json!({"counter_0": {"value": 3}});
+    persistence.persist(&state, 20_000);
+    assert_eq!(state.info, read(&files.info));
+
+    fs::remove_dir_all(files.functions.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn recover_interrupted_save() {
+    let files = state_files("recover");
+    let mut state = EngineState::default();
+    files.save(&state).unwrap();
+
+    // Crash before the journal, the previous files are kept
+    fs::write(temporary(&files.functions), b"[{\"name\": \"relay_on\"}]").unwrap();
+    files.recover().unwrap();
+    assert_eq!(json!([]), read(&files.functions));
+    assert!(!temporary(&files.functions).exists());
+
+    // Crash after the journal, the save is completed
+    state.info = json!({"counter_0": {"value": 3}});
+    fs::write(temporary(&files.functions), b"[{\"name\": \"relay_on\"}]").unwrap();
+    fs::write(
+        temporary(&files.info),
+        serde_json::to_vec(&state.info).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        &files.journal,
+        serde_json::to_vec(&json!([files.functions, files.info])).unwrap(),
+    )
+    .unwrap();
+    files.recover().unwrap();
+    assert_eq!(json!([{"name": "relay_on"}]), read(&files.functions));
+    assert_eq!(state.info, read(&files.info));
+    assert!(!files.journal.exists());
+
+    fs::remove_dir_all(files.functions.parent().unwrap()).unwrap();
+}
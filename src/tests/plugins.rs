@@ -0,0 +1,168 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::env;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::json;
+
+use crate::master::{
+    EngineAction, EngineState, FunctionOrigin, FunctionOrigins, MasterEngine, ReducerFunction,
+    ScriptSources,
+};
+use crate::plugins::native::{check_manifest, load_native_plugin, PluginManifest};
+use crate::plugins::{register_functions, PluginFunctions};
+use crate::rules;
+use crate::runtime::Engine;
+
+fn manifest(abi: u32, engine: &str) -> PluginManifest {
+    PluginManifest {
+        name: "test".into(),
+        version: "1.0.0".into(),
+        abi,
+        engine: engine.into(),
+        functions: vec!["native_test".into()],
+    }
+}
+
+#[test]
+fn native_plugin_versions() {
+    assert!(check_manifest(&manifest(1, env!("CARGO_PKG_VERSION"))).is_ok());
+    assert!(check_manifest(&manifest(1, "0.1")).is_ok());
+    assert!(check_manifest(&manifest(2, env!("CARGO_PKG_VERSION"))).is_err());
+    assert!(check_manifest(&manifest(1, "0.2.0")).is_err());
+    assert!(check_manifest(&manifest(1, "1.0.0")).is_err());
+    assert!(check_manifest(&manifest(1, "latest")).is_err());
+
+    assert!(load_native_plugin(Path::new("./plugins/not_found.so")).is_err());
+}
+
+#[test]
+fn plugin_functions_describe() {
+    let mut engine_functions = rules::distributed_engine_functions();
+    let mut origins = FunctionOrigins::new();
+    let wasm = FunctionOrigin::Wasm {
+        path: "plugins/test.wasm".into(),
+    };
+    let rejected = register_functions(
+        &mut engine_functions,
+        &mut origins,
+        vec![PluginFunctions {
+            origin: wasm.clone(),
+            functions: vec![
+                ("plugin_test".into(), rules::forward::forward_user_action()),
+                (
+                    "forward_user_action".into(),
+                    rules::forward::forward_user_action(),
+                ),
+            ],
+        }],
+    );
+    assert_eq!(vec!["forward_user_action".to_string()], rejected);
+    assert_eq!(Some(&wasm), origins.get("plugin_test"));
+    assert_eq!(None, origins.get("forward_user_action"));
+
+    let engine =
+        MasterEngine::new("MYRULESTEST".into(), engine_functions).with_function_origins(origins);
+    let (_, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new(
+            "MYRULESTEST/command/functions_describe".into(),
+            b"".to_vec(),
+        ),
+    );
+    assert_eq!(
+        "MYRULESTEST/notify/functions_describe",
+        result.messages[0].topic
+    );
+    let described = result.messages[0].payload_into_json().unwrap();
+    let described = described.as_array().unwrap();
+    assert!(described.contains(&json!({"name": "forward_user_action", "origin": "builtin"})));
+    assert!(described
+        .contains(&json!({"name": "plugin_test", "origin": "wasm", "path": "plugins/test.wasm"})));
+}
+
+// Builds the test plugin with the rustc that builds the tests
+fn build_native_plugin() -> PathBuf {
+    let directory = env::temp_dir().join(format!("myrulesiot-native-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let library = directory.join(format!("{DLL_PREFIX}native_test{DLL_SUFFIX}"));
+    let status = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+        .args(["--edition", "2021", "--crate-type", "cdylib", "-o"])
+        .arg(&library)
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/fixtures/native_plugin.rs"))
+        .env("MYRULESIOT_VERSION", env!("CARGO_PKG_VERSION"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    library
+}
+
+#[test]
+fn native_plugin_calls() {
+    let library = build_native_plugin();
+    let (manifest, functions) = load_native_plugin(&library).unwrap();
+    assert_eq!("test", manifest.name);
+
+    let mut engine_functions = rules::distributed_engine_functions();
+    let mut origins = FunctionOrigins::new();
+    let rejected = register_functions(
+        &mut engine_functions,
+        &mut origins,
+        vec![PluginFunctions {
+            origin: FunctionOrigin::Native {
+                path: library.display().to_string(),
+                plugin: manifest.name,
+                version: manifest.version,
+            },
+            functions,
+        }],
+    );
+    assert!(rejected.is_empty());
+    let engine = MasterEngine::new("MYRULESTEST".into(), engine_functions);
+    let functions: Vec<ReducerFunction> = serde_json::from_value(json!([
+        {"name": "native_echo"},
+        {"name": "native_echo"},
+        {"name": "native_fail"}
+    ]))
+    .unwrap();
+
+    let mut info = json!({});
+    let messages = engine.reduce_functions(
+        &functions,
+        &ScriptSources::new(),
+        &mut info,
+        &EngineAction::new("test/in".into(), b"hello".to_vec()),
+        0,
+    );
+    assert_eq!(3, messages.len());
+    assert_eq!("native/out", messages[0].topic);
+    assert_eq!(
+        json!({"topic": "test/in", "payload": "hello", "json": null}),
+        messages[0].payload_into_json().unwrap()["action"]
+    );
+    // Every result is released by the plugin
+    assert_eq!(json!(1), info["frees"]);
+    assert_eq!("MYRULESTEST/notify/system_error", messages[2].topic);
+
+    fs::remove_dir_all(library.parent().unwrap()).unwrap();
+}
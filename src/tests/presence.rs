@@ -0,0 +1,95 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Local, TimeZone};
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn presence_modes() {
+    let mut tester = ReduceTester::new(json!([
+        {
+            "name": "presence",
+            "_trackers": {
+                "alice": "owntracks/alice",
+                "bob": {"topic": "zigbee2mqtt/bob_tag", "pointer": "/presence", "home": true}
+            },
+            "_doors": ["zigbee2mqtt/front_door"],
+            "_manual_topic": "presence/set",
+            "_night": {"from": "23:00", "to": "07:00"},
+            "_forwardtopic": "presence/mode",
+            "_vacation_after_millis": 86_400_000
+        },
+        {"name": "forward_user_action", "_topic": "zigbee2mqtt/motion", "_forwardtopic": "alarm/motion", "_modes": ["away", "vacation"]},
+        {"name": "forward_user_action", "_topic": "zigbee2mqtt/motion", "_forwardtopic": "hall/light", "_modes": ["home", "night"]}
+    ]));
+    let minute = 60_000;
+    // 2026-10-19 12:00 local time
+    let noon = Local
+        .with_ymd_and_hms(2026, 10, 19, 12, 0, 0)
+        .unwrap()
+        .timestamp_millis();
+    let mut send =
+        |topic: &str, payload: &str, timestamp: i64| tester.send(topic, payload, noon + timestamp);
+
+    assert!(send("owntracks/alice", "home", 0).is_empty());
+    assert!(send("zigbee2mqtt/bob_tag", r#"{"presence": false}"#, 0).is_empty());
+    assert_eq!(
+        vec!["hall/light on"],
+        send("zigbee2mqtt/motion", "on", minute)
+    );
+
+    // Away after the delay, the door resets it
+    assert!(send("owntracks/alice", "not_home", 2 * minute).is_empty());
+    assert!(send("zigbee2mqtt/front_door", "{}", 5 * minute).is_empty());
+    assert!(send("SYSMR/action/tick", "", 14 * minute).is_empty());
+    assert_eq!(
+        vec!["presence/mode away"],
+        send("SYSMR/action/tick", "", 15 * minute)
+    );
+    assert_eq!(
+        vec!["alarm/motion on"],
+        send("zigbee2mqtt/motion", "on", 16 * minute)
+    );
+
+    // Night when back home late
+    assert_eq!(
+        vec!["presence/mode night"],
+        send(
+            "zigbee2mqtt/bob_tag",
+            r#"{"presence": true}"#,
+            11 * 60 * minute + 30 * minute
+        )
+    );
+
+    // Manual mode until auto
+    assert_eq!(
+        vec!["presence/mode vacation"],
+        send("presence/set", "vacation", 12 * 60 * minute)
+    );
+    assert_eq!(
+        vec!["alarm/motion on"],
+        send("zigbee2mqtt/motion", "on", 12 * 60 * minute)
+    );
+    assert_eq!(
+        vec!["presence/mode night"],
+        send("presence/set", "auto", 12 * 60 * minute)
+    );
+}
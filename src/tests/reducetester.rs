@@ -0,0 +1,93 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::Value;
+
+use crate::master::{EngineAction, EngineMessage, MasterEngine, ReducerFunction, ScriptSources};
+use crate::rules;
+
+// Runs a list of functions over its own info, without the runtime loop, so the
+// tests control the timestamp of each action
+pub struct ReduceTester {
+    engine: MasterEngine,
+    functions: Vec<ReducerFunction>,
+    pub info: Value,
+}
+
+impl ReduceTester {
+    pub fn new(functions: Value) -> Self {
+        ReduceTester {
+            engine: MasterEngine::new(
+                String::from("MYRULESTEST"),
+                rules::distributed_engine_functions(),
+            ),
+            functions: serde_json::from_value(functions).unwrap(),
+            info: Value::Object(Default::default()),
+        }
+    }
+
+    pub fn with_info(mut self, info: Value) -> Self {
+        self.info = info;
+        self
+    }
+
+    pub fn reduce(&mut self, action: &EngineAction, timestamp: i64) -> Vec<EngineMessage> {
+        self.engine.reduce_functions(
+            &self.functions,
+            &ScriptSources::new(),
+            &mut self.info,
+            action,
+            timestamp,
+        )
+    }
+
+    // Returns the published messages as "topic payload" lines
+    pub fn send(&mut self, topic: &str, payload: &str, timestamp: i64) -> Vec<String> {
+        let action = EngineAction::new(topic.into(), payload.into());
+        self.reduce(&action, timestamp)
+            .iter()
+            .map(message_line)
+            .collect()
+    }
+
+    pub fn send_json(&mut self, topic: &str, payload: Value, timestamp: i64) -> Vec<String> {
+        let action = EngineAction::new_json(topic.into(), payload);
+        self.reduce(&action, timestamp)
+            .iter()
+            .map(message_line)
+            .collect()
+    }
+
+    pub fn tick(&mut self, timestamp: i64) -> Vec<String> {
+        self.send("SYSMR/action/tick", "", timestamp)
+    }
+
+    // Reloads the info from its JSON text like after a restart
+    pub fn restart(&mut self) {
+        self.info = serde_json::from_str(&self.info.to_string()).unwrap();
+    }
+}
+
+pub fn message_line(message: &EngineMessage) -> String {
+    format!(
+        "{} {}",
+        message.topic,
+        String::from_utf8_lossy(&message.payload)
+    )
+}
@@ -0,0 +1,101 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::rules;
+
+use super::reducetester::ReduceTester;
+
+fn press(tester: &mut ReduceTester, action: &str, timestamp: i64) -> Vec<String> {
+    tester.send_json("zigbee2mqtt/remote", json!({ "action": action }), timestamp)
+}
+
+#[test]
+fn remote_profiles_and_double_click() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "ikea", "_action": "toggle", "_wait_double": true},
+        {"name": "relay", "_topic": "light", "_value": "toggle"},
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "ikea", "_action": "toggle_double"},
+        {"name": "relay", "_topic": "scene", "_value": "all_off"},
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": {"actions": {"left": "arrow_left_click"}}, "_action": "left"},
+        {"name": "relay", "_topic": "left", "_value": "click"}
+    ]));
+
+    // The single click waits for the double click window
+    assert!(press(&mut tester, "toggle", 0).is_empty());
+    assert_eq!(vec!["scene all_off"], press(&mut tester, "toggle", 300));
+    assert!(tester.tick(1000).is_empty());
+    assert!(press(&mut tester, "toggle", 2000).is_empty());
+    assert!(tester.tick(2500).is_empty());
+    assert_eq!(vec!["light toggle"], tester.tick(2600));
+    assert_eq!(
+        vec!["left click"],
+        press(&mut tester, "arrow_left_click", 4000)
+    );
+}
+
+#[test]
+fn remote_hold_and_release() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "hue_dimmer", "_action": "up_hold"},
+        {"name": "relay", "_topic": "dimmer", "_value": "start"},
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "hue_dimmer", "_action": "up_release"},
+        {"name": "publish_template", "_forwardtopic": "dimmer", "_payload": "stop {{info:/_hold_millis}}"}
+    ]));
+
+    assert_eq!(vec!["dimmer start"], press(&mut tester, "up_hold", 1000));
+    assert!(press(&mut tester, "up_press_release", 1500).is_empty());
+    assert_eq!(
+        vec!["dimmer stop 1800"],
+        press(&mut tester, "up_hold_release", 2800)
+    );
+}
+
+#[test]
+fn remote_single_button_hold() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "aqara", "_action": "release"},
+        {"name": "publish_template", "_forwardtopic": "dimmer", "_payload": "stop {{info:/_hold_millis}}"}
+    ]));
+
+    assert!(press(&mut tester, "hold", 1000).is_empty());
+    assert_eq!(
+        vec!["dimmer stop 2500"],
+        press(&mut tester, "release", 3500)
+    );
+}
+
+#[test]
+fn remote_requires_profile() {
+    let specs = rules::distributed_parameter_specs();
+    assert_eq!(
+        vec!["Missing parameter _profile"],
+        specs["start_remote"].check(&json!({"_topic": "zigbee2mqtt/remote", "_action": "toggle"}))
+    );
+
+    // Without a known profile the rule does not start
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_action": "toggle"},
+        {"name": "relay", "_topic": "light", "_value": "toggle"},
+        {"name": "start_remote", "_topic": "zigbee2mqtt/remote", "_profile": "unknown", "_action": "toggle"},
+        {"name": "relay", "_topic": "scene", "_value": "toggle"}
+    ]));
+    assert!(press(&mut tester, "toggle", 0).is_empty());
+}
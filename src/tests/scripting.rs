@@ -0,0 +1,170 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{EngineAction, EngineState, MasterEngine, ScriptLimits, ScriptRuntime};
+use crate::rules;
+use crate::runtime::Engine;
+
+const COUNTER_SCRIPT: &str = r#"
+fn slice(info, action) {
+    if action.topic != info._topic {
+        return ();
+    }
+    let count = (info.script_count ?? 0) + 1;
+    #{
+        state: #{ script_count: count },
+        messages: [#{ topic: info._forwardtopic, payload: `${action.json.name}:${count}` }]
+    }
+}
+"#;
+
+#[test]
+fn script_functions() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let (state, result) = engine.reduce(
+        EngineState::default(),
+        EngineAction::new_json(
+            "MYRULESTEST/command/scripts_put".into(),
+            json!({"name": "count_and_forward", "source": COUNTER_SCRIPT}),
+        ),
+    );
+    assert_eq!(
+        json!({"success": true, "name": "count_and_forward"}),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    // Scripts are validated like built-in functions
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/functions_batch".into(),
+            json!([{"op": "add", "function": {
+                "name": "count_and_forward",
+                "_topic": "button",
+                "_forwardtopic": "counter"
+            }}]),
+        ),
+    );
+    assert_eq!(
+        json!(true),
+        result.messages[0].payload_into_json().unwrap()["success"]
+    );
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json("button".into(), json!({"name": "kitchen"})),
+    );
+    assert_eq!(result.messages[0].topic, "counter");
+    assert_eq!(result.messages[0].payload, b"kitchen:1");
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json("button".into(), json!({"name": "kitchen"})),
+    );
+    assert_eq!(result.messages[0].payload, b"kitchen:2");
+    assert_eq!(json!({"script_count": 2}), state.info);
+
+    let (state, result) = engine.reduce(state, EngineAction::new("other".into(), vec![]));
+    assert!(result.messages.is_empty());
+
+    // Scripts in use cannot be removed
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new(
+            "MYRULESTEST/command/scripts_remove".into(),
+            b"count_and_forward".into(),
+        ),
+    );
+    assert_eq!(
+        json!({
+            "command": "scripts_remove",
+            "error": "Script count_and_forward used by 1 functions"
+        }),
+        result.messages[0].payload_into_json().unwrap()
+    );
+    assert_eq!(state.scripts.len(), 1);
+
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/functions_clear".into(), vec![]),
+    );
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new(
+            "MYRULESTEST/command/scripts_remove".into(),
+            b"count_and_forward".into(),
+        ),
+    );
+    assert!(state.scripts.is_empty());
+}
+
+#[test]
+fn script_cache_eviction() {
+    let runtime = ScriptRuntime::new(ScriptLimits::default());
+    let action = EngineAction::new("button".into(), vec![]);
+    runtime
+        .run("count_and_forward", COUNTER_SCRIPT, &json!({}), &action)
+        .unwrap();
+
+    assert!(runtime.evict("count_and_forward"));
+    assert!(!runtime.evict("count_and_forward"));
+}
+
+#[test]
+fn script_errors() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let put = |state: EngineState, name: &str, source: &str| {
+        engine.reduce(
+            state,
+            EngineAction::new_json(
+                "MYRULESTEST/command/scripts_put".into(),
+                json!({"name": name, "source": source}),
+            ),
+        )
+    };
+
+    let (state, result) = put(EngineState::default(), "relay_on", COUNTER_SCRIPT);
+    assert_eq!(
+        json!("Function already exists: relay_on"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+    let (state, result) = put(state, "no_entry", "fn other(a) { a }");
+    assert_eq!(
+        json!("Script must define the function slice(info, action)"),
+        result.messages[0].payload_into_json().unwrap()["error"]
+    );
+    let (state, result) = put(state, "forever", "fn slice(info, action) { loop { } }");
+    assert_eq!(
+        json!({"success": true, "name": "forever"}),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    let state = EngineState {
+        functions: serde_json::from_value(json!([{"name": "forever"}])).unwrap(),
+        ..state
+    };
+    let (_, result) = engine.reduce(state, EngineAction::new("any".into(), vec![]));
+    assert_eq!(result.messages[0].topic, "MYRULESTEST/notify/system_error");
+    assert!(String::from_utf8_lossy(&result.messages[0].payload)
+        .starts_with("Script forever failed: Runtime error"));
+}
@@ -0,0 +1,116 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::EngineAction;
+
+use super::reducetester::ReduceTester;
+
+const SECOND: i64 = 1_000;
+
+#[test]
+fn sequence_steps() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "sequence",
+        "_topic": "house/+/leave",
+        "_cancel_topic": "house/cancel",
+        "_steps": [
+            {"publish": "blinds/{{wildcard:0}}/set", "payload": "close"},
+            {"wait_millis": 30 * SECOND},
+            {"publish": "lights/set", "payload": {"state": "off", "by": "{{payload:/user}}"}},
+            {"wait_millis": 300 * SECOND},
+            {"condition": "info.armable"},
+            {"publish": "alarm/keypad", "payload": {"action": "arm_away"}}
+        ]
+    }]))
+    .with_info(json!({ "armable": true }));
+
+    assert_eq!(
+        vec!["blinds/living/set close"],
+        tester.send_json("house/living/leave", json!({"user": "ana"}), 0)
+    );
+    assert!(tester.tick(29 * SECOND).is_empty());
+    assert_eq!(
+        vec![r#"lights/set {"by":"ana","state":"off"}"#],
+        tester.tick(30 * SECOND)
+    );
+
+    // Restarts keep the running step and run the steps due meanwhile
+    tester.restart();
+    assert_eq!(
+        vec![r#"alarm/keypad {"action":"arm_away"}"#],
+        tester.tick(500 * SECOND)
+    );
+    assert!(tester.info["sequence_0"].is_null());
+
+    // Retrigger restarts from the first step
+    tester.send_json("house/living/leave", json!({"user": "ana"}), 600 * SECOND);
+    assert_eq!(
+        vec!["blinds/hall/set close"],
+        tester.send_json("house/hall/leave", json!({"user": "bob"}), 620 * SECOND)
+    );
+    assert!(tester.tick(630 * SECOND).is_empty());
+    assert_eq!(
+        vec![r#"lights/set {"by":"bob","state":"off"}"#],
+        tester.tick(650 * SECOND)
+    );
+
+    // Cancelled
+    tester.send_json("house/cancel", json!(null), 700 * SECOND);
+    assert!(tester.tick(1000 * SECOND).is_empty());
+
+    // Failed condition ends the sequence
+    tester.info["armable"] = json!(false);
+    tester.send_json("house/living/leave", json!({}), 2000 * SECOND);
+    tester.tick(2030 * SECOND);
+    assert!(tester.tick(2330 * SECOND).is_empty());
+    assert!(tester.info["sequence_0"].is_null());
+}
+
+#[test]
+fn sequence_binary_trigger() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "sequence", "_topic": "broken"},
+        {
+            "name": "sequence",
+            "_topic": "sensor/raw",
+            "_steps": [{"wait_millis": 10 * SECOND}, {"publish": "sensor/done", "payload": "done"}]
+        }
+    ]));
+
+    // Binary payloads are kept as hex, text ones replace them
+    tester.send("sensor/raw", "", 0);
+    tester.reduce(
+        &EngineAction::new("sensor/raw".into(), vec![0xff, 0x00]),
+        1_000,
+    );
+    assert_eq!(
+        json!({"topic": "sensor/raw", "payload_hex": "ff00"}),
+        tester.info["sequence_1"]["trigger"]
+    );
+    tester.send("sensor/raw", "text", 2_000);
+    assert_eq!(
+        json!({"topic": "sensor/raw", "payload": "text"}),
+        tester.info["sequence_1"]["trigger"]
+    );
+    assert_eq!(vec!["sensor/done done"], tester.tick(12 * SECOND));
+    // Without _steps nothing runs
+    assert!(tester.send("broken", "", 13 * SECOND).is_empty());
+}
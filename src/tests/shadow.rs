@@ -0,0 +1,102 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::{EngineAction, EngineState, MasterEngine};
+use crate::rules;
+use crate::runtime::Engine;
+
+#[test]
+fn shadow_divergences() {
+    let engine = MasterEngine::new("MYRULESTEST".into(), rules::distributed_engine_functions());
+
+    let state = EngineState::new_functions(
+        serde_json::from_value(json!([
+            {"name": "forward_user_action", "_topic": "sensor", "_forwardtopic": "display"}
+        ]))
+        .unwrap(),
+    );
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new_json(
+            "MYRULESTEST/command/shadow_putall".into(),
+            json!({
+                "functions": [
+                    {"name": "forward_user_action", "_topic": "sensor", "_forwardtopic": "display"},
+                    {"name": "forward_user_action", "_topic": "sensor", "_forwardtopic": "log"}
+                ],
+                "report_interval": 0
+            }),
+        ),
+    );
+    assert_eq!(
+        json!({"success": true, "count": 2}),
+        result.messages[0].payload_into_json().unwrap()
+    );
+
+    // Live messages and diverted shadow messages
+    let (state, result) = engine.reduce(state, EngineAction::new("sensor".into(), b"21".into()));
+    assert_eq!(
+        vec![
+            "display",
+            "MYRULESTEST/shadow/display",
+            "MYRULESTEST/shadow/log"
+        ],
+        result
+            .messages
+            .iter()
+            .map(|m| m.topic.as_str())
+            .collect::<Vec<&str>>()
+    );
+
+    // Same output in both
+    let (state, result) = engine.reduce(state, EngineAction::new("other".into(), b"21".into()));
+    assert_eq!(result.messages.len(), 0);
+
+    let (state, result) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/shadow_report".into(), vec![]),
+    );
+    let report = result.messages[0].payload_into_json().unwrap();
+    assert_eq!(json!(2), report["actions"]);
+    assert_eq!(json!(1), report["diverged"]);
+    assert_eq!(
+        json!({
+            "topic": "sensor",
+            "live": [{"topic": "display", "payload": 21, "properties": null}],
+            "shadow": [
+                {"topic": "display", "payload": 21, "properties": null},
+                {"topic": "log", "payload": 21, "properties": null}
+            ]
+        }),
+        json!({
+            "topic": report["divergences"][0]["topic"],
+            "live": report["divergences"][0]["live"],
+            "shadow": report["divergences"][0]["shadow"]
+        })
+    );
+
+    let (state, _) = engine.reduce(
+        state,
+        EngineAction::new("MYRULESTEST/command/shadow_clear".into(), vec![]),
+    );
+    assert!(state.shadow.is_none());
+}
@@ -0,0 +1,86 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+const WEEK: i64 = 7 * 24 * 3_600_000;
+const MINUTE: i64 = 60_000;
+
+#[test]
+fn presence_simulation_replay() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "presence_simulation",
+        "_topics": {"shellies/lamp/relay/0": "shellies/lamp/relay/0/command"},
+        "_jitter_millis": 10 * MINUTE
+    }]))
+    .with_info(json!({"presence": {"mode": "home"}}));
+    let start = 1_000 * WEEK;
+    let events = |tester: &ReduceTester| {
+        tester.info["presence_simulation_0"]["events"]
+            .as_array()
+            .unwrap()
+            .len()
+    };
+
+    tester.send("shellies/lamp/relay/0", "on", start);
+    tester.send("shellies/other", "on", start + MINUTE);
+    // Repeated reports of the same state are recorded once
+    tester.send("shellies/lamp/relay/0", "on", start + 30 * MINUTE);
+    tester.send("shellies/lamp/relay/0", "off", start + 60 * MINUTE);
+    assert_eq!(2, events(&tester));
+
+    // Replays every event once a week later within the jitter
+    tester.info["presence"]["mode"] = json!("vacation");
+    let mut replayed = vec![];
+    let mut timestamp = start + WEEK - 30 * MINUTE;
+    while timestamp < start + WEEK + 120 * MINUTE {
+        for message in tester.tick(timestamp) {
+            replayed.push((timestamp - start - WEEK, message));
+        }
+        timestamp += 15_000;
+    }
+    assert_eq!(2, replayed.len());
+    assert!(replayed[0].0.abs() <= 10 * MINUTE);
+    assert_eq!("shellies/lamp/relay/0/command on", replayed[0].1);
+    assert!((replayed[1].0 - 60 * MINUTE).abs() <= 10 * MINUTE);
+    assert_eq!("shellies/lamp/relay/0/command off", replayed[1].1);
+
+    // Replayed commands are not recorded
+    tester.send("shellies/lamp/relay/0", "on", start + WEEK + 130 * MINUTE);
+    assert_eq!(2, events(&tester));
+}
+
+#[test]
+fn presence_simulation_unknown_topic() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "presence_simulation",
+        "_topics": {"shellies/lamp/relay/0": "shellies/lamp/relay/0/command"}
+    }]))
+    .with_info(json!({
+        "presence": {"mode": "vacation"},
+        "presence_simulation_0": {
+            "events": [{"at": 0, "topic": "shellies/removed/relay/0", "payload": "on"}]
+        }
+    }));
+
+    // Events of topics no longer configured are not replayed
+    assert!(tester.tick(WEEK).is_empty());
+}
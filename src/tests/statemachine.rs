@@ -0,0 +1,115 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn state_machine_transitions() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "state_machine",
+        "_initial": "idle",
+        "_statetopic": "washer/state",
+        "_states": {
+            "idle": {},
+            "running": {
+                "entry": [{"topic": "washer/notify", "payload": "started at {{payload:/power}} W"}]
+            },
+            "finished": {
+                "entry": [{"topic": "washer/notify", "payload": {"done": true, "room": "{{wildcard:0}}"}}],
+                "exit": [{"topic": "washer/notify", "payload": "emptied"}]
+            }
+        },
+        "_transitions": [
+            {"from": "idle", "to": "running", "topic": "zigbee2mqtt/plug", "expression": "payload.power > 10"},
+            {"from": "running", "to": "finished", "topic": "zigbee2mqtt/+/washer", "pointer": "/state", "payload": "stopped"},
+            {"from": "finished", "to": "idle", "after_millis": 60_000},
+            {"from": "*", "to": "idle", "topic": "washer/reset", "actions": [{"topic": "washer/notify", "payload": "reset"}]}
+        ]
+    }]));
+    let mut send =
+        |topic: &str, payload: Value, timestamp: i64| tester.send_json(topic, payload, timestamp);
+
+    // The initial state is published on the first action
+    assert_eq!(
+        vec!["washer/state idle"],
+        send("zigbee2mqtt/plug", json!({"power": 2}), 0)
+    );
+    assert_eq!(
+        vec!["washer/notify started at 1500 W", "washer/state running"],
+        send("zigbee2mqtt/plug", json!({"power": 1500}), 1_000)
+    );
+    assert!(send("zigbee2mqtt/plug", json!({"power": 1}), 2_000).is_empty());
+    assert!(send(
+        "zigbee2mqtt/laundry/washer",
+        json!({"state": "running"}),
+        3_000
+    )
+    .is_empty());
+    assert_eq!(
+        vec![
+            r#"washer/notify {"done":true,"room":"laundry"}"#,
+            "washer/state finished"
+        ],
+        send(
+            "zigbee2mqtt/laundry/washer",
+            json!({"state": "stopped"}),
+            4_000
+        )
+    );
+    assert!(send("SYSMR/action/tick", json!(null), 63_999).is_empty());
+    assert_eq!(
+        vec!["washer/notify emptied", "washer/state idle"],
+        send("SYSMR/action/tick", json!(null), 64_000)
+    );
+    assert_eq!(
+        vec!["washer/notify started at 20 W", "washer/state running"],
+        send("zigbee2mqtt/plug", json!({"power": 20}), 70_000)
+    );
+    assert_eq!(
+        vec!["washer/notify reset", "washer/state idle"],
+        send("washer/reset", json!(null), 80_000)
+    );
+    assert_eq!(
+        json!({"state": "idle", "since": 80_000}),
+        tester.info["state_machine_0"]
+    );
+}
+
+#[test]
+fn state_machine_invalid_definitions() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "state_machine", "_statetopic": "broken/state"},
+        {
+            "name": "state_machine",
+            "_initial": "off",
+            "_statetopic": "lamp/state",
+            "_transitions": [
+                {"from": "off", "topic": "lamp/toggle"},
+                {"from": "off", "to": "on", "topic": "lamp/toggle", "actions": [{"payload": "lost"}]}
+            ]
+        }
+    ]));
+
+    // Without _initial the machine is skipped, transitions without to are ignored
+    assert_eq!(vec!["lamp/state off"], tester.send("lamp/state", "", 0));
+    assert_eq!(vec!["lamp/state on"], tester.send("lamp/toggle", "", 1_000));
+    assert!(tester.info["state_machine_0"].is_null());
+}
@@ -0,0 +1,94 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use crate::master::EngineAction;
+use crate::rules::template::{match_topic, TemplateContext};
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn template_topic_wildcards() {
+    assert_eq!(Some(vec![]), match_topic("home/kitchen", "home/kitchen"));
+    assert_eq!(None, match_topic("home/kitchen", "home/kitchen/temp"));
+    assert_eq!(
+        Some(vec!["kitchen".to_string()]),
+        match_topic("home/+/temp", "home/kitchen/temp")
+    );
+    assert_eq!(None, match_topic("home/+/temp", "home/kitchen/humidity"));
+    assert_eq!(
+        Some(vec!["kitchen".to_string(), "sensor/temp".to_string()]),
+        match_topic("home/+/#", "home/kitchen/sensor/temp")
+    );
+    assert_eq!(Some(vec!["".to_string()]), match_topic("home/#", "home"));
+}
+
+#[test]
+fn publish_template_messages() {
+    let mut tester = ReduceTester::new(json!([
+        {
+            "name": "publish_template",
+            "_topic": "zigbee2mqtt/+/sensor",
+            "_forwardtopic": "home/{{wildcard:0}}/temperature",
+            "_payload": "{{payload:/temperature}} {{info:/unit}}",
+            "_retain": true
+        },
+        {
+            "name": "publish_template",
+            "_topic": "zigbee2mqtt/+/sensor",
+            "_forwardtopic": "log/{{topic:1}}",
+            "_payload": {"room": "{{wildcard:0}}", "value": "{{payload:/temperature}}", "at": "{{timestamp}}"},
+            "_qos": 0
+        }
+    ]))
+    .with_info(json!({"unit": "ºC"}));
+
+    let messages = tester.reduce(
+        &EngineAction::new_json(
+            "zigbee2mqtt/kitchen/sensor".into(),
+            json!({"temperature": 21.5}),
+        ),
+        0,
+    );
+    assert_eq!(2, messages.len());
+    assert_eq!("home/kitchen/temperature", messages[0].topic);
+    assert_eq!("21.5 ºC".as_bytes(), messages[0].payload);
+    assert_eq!(json!({"retain": true}), messages[0].properties);
+
+    assert_eq!("log/kitchen", messages[1].topic);
+    let payload = messages[1].payload_into_json().unwrap();
+    assert_eq!(json!("kitchen"), payload["room"]);
+    assert_eq!(json!(21.5), payload["value"]);
+    assert!(payload["at"].is_i64());
+    assert_eq!(json!({"qos": 0}), messages[1].properties);
+}
+
+#[test]
+fn template_invalid_time_format() {
+    let info = json!({});
+    let action = EngineAction::new("clock".into(), vec![]);
+    let context = TemplateContext {
+        info: &info,
+        action: &action,
+        wildcards: &[],
+    };
+    assert_eq!("at ", context.render("at {{now:%Q}}"));
+    assert_eq!(4, context.render("{{now:%Y}}").len());
+}
@@ -0,0 +1,99 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use chrono::{Local, TimeZone};
+use serde_json::json;
+
+use crate::rules::thermostat::schedule_setpoint;
+
+use super::reducetester::ReduceTester;
+
+fn local(day: u32, hour: u32, minute: u32) -> i64 {
+    // 2026-10-19 is a Monday
+    Local
+        .with_ymd_and_hms(2026, 10, 19 + day, hour, minute, 0)
+        .unwrap()
+        .timestamp_millis()
+}
+
+#[test]
+fn thermostat_weekly_schedule() {
+    let schedule = json!([
+        {"from": "06:30", "setpoint": 21},
+        {"from": "22:00", "setpoint": 17},
+        {"days": ["sat", "sun"], "from": "08:00", "setpoint": 22}
+    ]);
+    assert_eq!(Some(21.0), schedule_setpoint(&schedule, local(0, 7, 0)));
+    assert_eq!(Some(17.0), schedule_setpoint(&schedule, local(0, 23, 0)));
+    assert_eq!(Some(17.0), schedule_setpoint(&schedule, local(0, 3, 0)));
+    assert_eq!(Some(21.0), schedule_setpoint(&schedule, local(5, 7, 0)));
+    assert_eq!(Some(22.0), schedule_setpoint(&schedule, local(5, 9, 0)));
+    assert_eq!(None, schedule_setpoint(&json!([]), local(0, 7, 0)));
+}
+
+#[test]
+fn thermostat_minimum_times_and_override() {
+    let mut tester = ReduceTester::new(json!([{
+        "name": "thermostat",
+        "_topic": "zigbee2mqtt/livingroom",
+        "_forwardtopic": "shellies/boiler/relay/0/command",
+        "_override_topic": "thermostat/override",
+        "_statetopic": "thermostat/state",
+        "_setpoint": 20
+    }]));
+    let minute = 60_000;
+    let mut send =
+        |topic: &str, payload: &str, timestamp: i64| tester.send(topic, payload, timestamp);
+    let temperature = |t: f64| json!({ "temperature": t }).to_string();
+
+    assert_eq!(
+        vec![
+            "shellies/boiler/relay/0/command on",
+            r#"thermostat/state {"heating":true,"override":false,"setpoint":20.0,"temperature":19.0}"#
+        ],
+        send("zigbee2mqtt/livingroom", &temperature(19.0), 0)
+    );
+    // Minimum on time
+    assert!(send("zigbee2mqtt/livingroom", &temperature(21.0), minute).is_empty());
+    assert_eq!(
+        "shellies/boiler/relay/0/command off",
+        send("SYSMR/action/tick", "", 5 * minute)[0]
+    );
+    // Minimum off time
+    assert!(send("zigbee2mqtt/livingroom", &temperature(19.0), 6 * minute).is_empty());
+    assert_eq!(
+        "shellies/boiler/relay/0/command on",
+        send("SYSMR/action/tick", "", 10 * minute)[0]
+    );
+    assert_eq!(
+        vec![
+            r#"thermostat/state {"heating":true,"override":true,"setpoint":25.0,"temperature":19.0}"#
+        ],
+        send(
+            "thermostat/override",
+            r#"{"setpoint": 25, "minutes": 30}"#,
+            11 * minute
+        )
+    );
+    // No readings for too long
+    assert_eq!(
+        "shellies/boiler/relay/0/command off",
+        send("SYSMR/action/tick", "", 22 * minute)[0]
+    );
+}
@@ -0,0 +1,74 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::json;
+
+use super::reducetester::ReduceTester;
+
+#[test]
+fn threshold_hysteresis_fan() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_threshold_above", "_topic": "bathroom/sensor", "_pointer": "/humidity", "_value": 70, "_hysteresis": 10},
+        {"name": "relay_on", "_topic": "bathroom/fan"},
+        {"name": "start_threshold_below", "_topic": "bathroom/sensor", "_pointer": "/humidity", "_value": 60, "_hysteresis": 10},
+        {"name": "relay_off", "_topic": "bathroom/fan"}
+    ]));
+
+    let mut published = vec![];
+    for humidity in [65, 72, 75, 68, 62, 58, 55, 65, 71] {
+        published.push(
+            tester
+                .send_json("bathroom/sensor", json!({ "humidity": humidity }), 0)
+                .join(","),
+        );
+    }
+    // Fires only when crossing into the active side
+    assert_eq!(
+        vec![
+            "",
+            "bathroom/fan on",
+            "",
+            "",
+            "",
+            "bathroom/fan off",
+            "",
+            "",
+            "bathroom/fan on"
+        ],
+        published
+    );
+    assert_eq!(
+        json!({"active": true, "value": 71.0}),
+        tester.info["start_threshold_0"]
+    );
+}
+
+#[test]
+fn threshold_between_levels() {
+    let mut tester = ReduceTester::new(json!([
+        {"name": "start_threshold_between", "_topic": "tank", "_pointer": "/level", "_min": 20, "_max": 80, "_edge": false},
+        {"name": "relay_on", "_topic": "pump"}
+    ]));
+
+    let mut published = vec![];
+    for level in [10, 30, 50, 90] {
+        published.push(tester.send_json("tank", json!({ "level": level }), 0).len());
+    }
+    assert_eq!(vec![0, 1, 1, 0], published);
+}
@@ -0,0 +1,96 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use serde_json::{json, Value};
+
+use super::reducetester::ReduceTester;
+
+// Runs the condition between an input that starts with "on" and a relay
+fn condition(name: &str, mut parameters: Value) -> ReduceTester {
+    parameters["name"] = json!(name);
+    ReduceTester::new(json!([
+        {"name": "start_action", "_topic": "input", "_command": "on"},
+        parameters,
+        {"name": "relay_on", "_topic": "output"}
+    ]))
+}
+
+// Sends the input or a tick and returns whether the condition started
+fn step(tester: &mut ReduceTester, start: bool, timestamp: i64) -> bool {
+    let messages = if start {
+        tester.send("input", "on", timestamp)
+    } else {
+        tester.tick(timestamp)
+    };
+    !messages.is_empty()
+}
+
+#[test]
+fn debounce_trailing_and_leading() {
+    let mut tester = condition("condition_debounce", json!({"_millis": 1000}));
+    // A bouncing contact starts once after it settles
+    assert!(!step(&mut tester, true, 0));
+    assert!(!step(&mut tester, true, 200));
+    assert!(!step(&mut tester, true, 400));
+    assert!(!step(&mut tester, false, 1250));
+    assert!(step(&mut tester, false, 1500));
+    assert!(!step(&mut tester, false, 1750));
+    assert_eq!(json!(null), tester.info["condition_debounce_1"]);
+
+    let mut tester = condition(
+        "condition_debounce",
+        json!({"_millis": 1000, "_leading": true, "_trailing": false}),
+    );
+    assert!(step(&mut tester, true, 0));
+    assert!(!step(&mut tester, true, 500));
+    assert!(!step(&mut tester, false, 2000));
+    assert!(step(&mut tester, true, 2250));
+}
+
+#[test]
+fn throttle_windows() {
+    let mut tester = condition("condition_throttle", json!({"_millis": 1000}));
+    assert!(step(&mut tester, true, 0));
+    assert!(!step(&mut tester, true, 300));
+    assert!(!step(&mut tester, false, 1250));
+    assert!(step(&mut tester, true, 1500));
+
+    let mut tester = condition(
+        "condition_throttle",
+        json!({"_millis": 1000, "_trailing": true}),
+    );
+    assert!(step(&mut tester, true, 0));
+    assert!(!step(&mut tester, true, 300));
+    assert!(step(&mut tester, false, 1000));
+    // The trailing activation opened a new window
+    assert!(!step(&mut tester, true, 1500));
+}
+
+#[test]
+fn rate_limit_window() {
+    let mut tester = condition(
+        "condition_rate_limit",
+        json!({"_millis": 1000, "_count": 2}),
+    );
+    assert!(step(&mut tester, true, 0));
+    assert!(step(&mut tester, true, 100));
+    assert!(!step(&mut tester, true, 200));
+    assert!(step(&mut tester, true, 1050));
+    assert!(!step(&mut tester, true, 1060));
+}
@@ -0,0 +1,146 @@
+//    MyRulesIoT  Project is a rules engine for MQTT based on MyRulesIoT lib
+//    Copyright (C) 2025 Adrián Romero Corchado.
+//
+//    This file is part of MyRulesIoT.
+//
+//    MyRulesIoT is free software: you can redistribute it and/or modify
+//    it under the terms of the GNU General Public License as published by
+//    the Free Software Foundation, either version 3 of the License, or
+//    (at your option) any later version.
+//
+//    MyRulesIoT is distributed in the hope that it will be useful,
+//    but WITHOUT ANY WARRANTY; without even the implied warranty of
+//    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+//    GNU General Public License for more details.
+//
+//    You should have received a copy of the GNU General Public License
+//    along with MyRulesIoT.  If not, see <http://www.gnu.org/licenses/>.
+//
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use serde_json::json;
+
+use crate::master::{
+    EngineAction, FunctionOrigin, FunctionOrigins, MasterEngine, ReducerFunction, ScriptSources,
+    SliceFunction,
+};
+use crate::plugins::wasm::{load_wasm_plugin, WasmLimits};
+use crate::plugins::{self, PluginFunctions};
+use crate::rules;
+
+const PLUGIN: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (global $frees (mut i32) (i32.const 0))
+  (global $poisoned (mut i32) (i32.const 0))
+  (data (i32.const 0) "{\"messages\":[{\"topic\":\"wasm/out\",\"payload\":\"hello\"}]}")
+  (data (i32.const 64) "{\"state\":{\"freed\":true}}")
+  (data (i32.const 128) "null")
+  (func (export "alloc") (param i32) (result i32)
+    i32.const 1024)
+  (func (export "dealloc") (param i32 i32)
+    (global.set $frees (i32.add (global.get $frees) (i32.const 1))))
+  (func (export "slice_wasm_hello") (param i32 i32) (result i64)
+    (if (result i64) (global.get $poisoned)
+      (then (i64.const 549755813892))
+      (else (i64.const 53))))
+  (func (export "slice_wasm_frees") (param i32 i32) (result i64)
+    (if (result i64) (i32.ge_u (global.get $frees) (i32.const 2))
+      (then (i64.const 274877906968))
+      (else (i64.const 549755813892))))
+  (func (export "slice_wasm_trap") (param i32 i32) (result i64)
+    (global.set $poisoned (i32.const 1))
+    unreachable)
+  (func (export "slice_wasm_loop") (param i32 i32) (result i64)
+    (loop $forever (br $forever))
+    i64.const 0))
+"#;
+
+fn load_plugin(test: &str) -> HashMap<String, SliceFunction> {
+    let path = env::temp_dir().join(format!("myrulesiot_{test}_{}.wat", std::process::id()));
+    fs::write(&path, PLUGIN).unwrap();
+    let functions = load_wasm_plugin(&path, WasmLimits::default()).unwrap();
+    fs::remove_file(&path).unwrap();
+    functions.into_iter().collect()
+}
+
+#[test]
+fn wasm_plugin_functions() {
+    let functions = load_plugin("functions");
+
+    let mut names: Vec<&str> = functions.keys().map(String::as_str).collect();
+    names.sort();
+    assert_eq!(
+        vec!["wasm_frees", "wasm_hello", "wasm_loop", "wasm_trap"],
+        names
+    );
+
+    let action = EngineAction::new("test/in".into(), b"1".to_vec());
+    let result = functions["wasm_hello"](&json!({}), &action);
+    assert_eq!(1, result.messages.len());
+    assert_eq!("wasm/out", result.messages[0].topic);
+    assert_eq!(b"hello".to_vec(), result.messages[0].payload);
+
+    // Input and result buffers are released
+    let result = functions["wasm_frees"](&json!({}), &action);
+    assert_eq!(json!({"freed": true}), result.state);
+
+    // Runs out of fuel instead of blocking the engine
+    let result = functions["wasm_loop"](&json!({}), &action);
+    assert!(result.messages.is_empty());
+    assert!(result.error.is_some());
+
+    // A new instance replaces the one that trapped
+    let result = functions["wasm_trap"](&json!({}), &action);
+    assert!(result.error.is_some());
+    let result = functions["wasm_hello"](&json!({}), &action);
+    assert_eq!(1, result.messages.len());
+}
+
+#[test]
+fn wasm_plugin_input_released() {
+    let functions = load_plugin("released");
+    let action = EngineAction::new("test/in".into(), b"1".to_vec());
+
+    // The input does not fit in memory and cannot be written
+    let oversized = json!({ "data": "x".repeat(70_000) });
+    for _ in 0..2 {
+        let result = functions["wasm_hello"](&oversized, &action);
+        assert!(result.error.is_some());
+    }
+    let result = functions["wasm_frees"](&json!({}), &action);
+    assert_eq!(json!({"freed": true}), result.state);
+}
+
+#[test]
+fn wasm_plugin_errors_notified() {
+    let mut engine_functions = rules::distributed_engine_functions();
+    let mut origins = FunctionOrigins::new();
+    plugins::register_functions(
+        &mut engine_functions,
+        &mut origins,
+        vec![PluginFunctions {
+            origin: FunctionOrigin::Wasm {
+                path: "test.wat".into(),
+            },
+            functions: load_plugin("errors").into_iter().collect(),
+        }],
+    );
+    let engine = MasterEngine::new("MYRULESTEST".into(), engine_functions);
+    let functions: Vec<ReducerFunction> =
+        serde_json::from_value(json!([{"name": "wasm_trap"}])).unwrap();
+
+    let messages = engine.reduce_functions(
+        &functions,
+        &ScriptSources::new(),
+        &mut json!({}),
+        &EngineAction::new("test/in".into(), vec![]),
+        0,
+    );
+    assert_eq!(1, messages.len());
+    assert_eq!("MYRULESTEST/notify/system_error", messages[0].topic);
+    assert!(String::from_utf8_lossy(&messages[0].payload).starts_with("Function wasm_trap failed"));
+}